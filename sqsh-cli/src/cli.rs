@@ -12,6 +12,10 @@ pub struct Cli {
     /// Control verbose output (e.g. -vv [Info])
     #[clap(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// Process the input twice and fail if the outputs are not identical
+    #[clap(long, global = true)]
+    pub deterministic: bool,
 }
 
 /// Commands to be executed by the CLI
@@ -26,6 +30,14 @@ pub enum Commands {
         /// Output file
         #[clap(value_parser)]
         output: Option<PathBuf>,
+
+        /// Keep the input file after processing (default)
+        #[clap(long, conflicts_with = "remove-input")]
+        keep: bool,
+
+        /// Remove the input file after successfully writing the output file
+        #[clap(long, requires = "output")]
+        remove_input: bool,
     },
     /// Calculate Adler32 checksum
     Adler32 {
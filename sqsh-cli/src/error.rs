@@ -0,0 +1,89 @@
+use sqsh::core::ChecksumMismatch;
+use std::fmt::Display;
+use std::io::{Error, ErrorKind};
+
+/// Errors reported by the CLI, each mapped to a distinct exit code
+#[derive(Debug)]
+pub(crate) enum CliError {
+    /// Reading or writing of the data failed
+    Io(Error),
+    /// The input data could not be processed
+    InvalidData(sqsh::Error),
+    /// The calculated checksum differs from the expected one
+    ChecksumMismatch(ChecksumMismatch),
+}
+
+impl CliError {
+    /// Exit code of the process for this error
+    ///
+    /// Exit code 2 is left to clap, which uses it for invalid arguments.
+    /// Invalid data uses `EX_DATAERR` of `sysexits.h`.
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Io(_) => 1,
+            CliError::ChecksumMismatch(_) => 3,
+            CliError::InvalidData(_) => 65,
+        }
+    }
+}
+
+impl From<sqsh::Error> for CliError {
+    fn from(e: sqsh::Error) -> Self {
+        match e {
+            sqsh::Error::Io(e) => match e.kind() {
+                ErrorKind::InvalidData | ErrorKind::UnexpectedEof => {
+                    CliError::InvalidData(sqsh::Error::Io(e))
+                }
+                _ => CliError::Io(e),
+            },
+            sqsh::Error::ChecksumMismatch(mismatch) => CliError::ChecksumMismatch(mismatch),
+            sqsh::Error::Truncated(_) | sqsh::Error::InvalidCode(_) => CliError::InvalidData(e),
+        }
+    }
+}
+
+/// I/O errors carrying a processor error are mapped by the inner error
+impl From<Error> for CliError {
+    fn from(e: Error) -> Self {
+        sqsh::Error::from(e).into()
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Io(e) => write!(f, "I/O error: {e}"),
+            CliError::InvalidData(e) => write!(f, "Invalid input data: {e}"),
+            CliError::ChecksumMismatch(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes() {
+        let io: CliError = Error::new(ErrorKind::NotFound, "missing").into();
+        assert_eq!(io.exit_code(), 1);
+        assert_eq!(io.to_string(), "I/O error: missing");
+        let invalid: CliError = sqsh::Error::invalid_code("corrupt").into();
+        assert_eq!(invalid.exit_code(), 65);
+        assert_eq!(invalid.to_string(), "Invalid input data: corrupt");
+        let truncated: CliError = Error::from(sqsh::Error::truncated("short")).into();
+        assert_eq!(truncated.exit_code(), 65);
+        let nondeterministic: CliError = Error::new(ErrorKind::InvalidData, "differ").into();
+        assert_eq!(nondeterministic.exit_code(), 65);
+        let mismatch = ChecksumMismatch {
+            expected: 1,
+            calculated: 2,
+        };
+        let checksum: CliError = sqsh::Error::ChecksumMismatch(mismatch).into();
+        assert_eq!(checksum.exit_code(), 3);
+        assert_eq!(
+            checksum.to_string(),
+            "Checksum mismatch: expected 0x1, calculated 0x2"
+        );
+    }
+}
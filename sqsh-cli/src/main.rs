@@ -1,11 +1,20 @@
 use clap::Parser;
-use log::debug;
+use error::CliError;
+use log::{debug, info};
 use sqsh::processors::{Adler32, Duplicate, CRC32};
-use utils::{generate_file_stream, generate_stdout_stream};
+use utils::{assert_deterministic, generate_file_stream, generate_stdout_stream};
 mod cli;
+mod error;
 mod utils;
 
-fn main() -> std::io::Result<()> {
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("sqsh: {e}");
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run() -> Result<(), CliError> {
     let args = cli::Cli::parse();
     env_logger::Builder::new()
         .filter_level(args.verbose.log_level_filter())
@@ -13,20 +22,42 @@ fn main() -> std::io::Result<()> {
     debug!("Configuration: {args:?}");
 
     match args.command {
-        cli::Commands::Duplicate { input, output } => {
+        cli::Commands::Duplicate {
+            input,
+            output,
+            remove_input,
+            ..
+        } => {
+            if args.deterministic {
+                assert_deterministic::<Duplicate>(&input)?;
+            }
             if let Some(path) = output {
-                let mut stream = generate_file_stream::<Duplicate>(input, path)?;
-                stream.consume()?;
+                let mut stream = generate_file_stream::<Duplicate>(input.clone(), path)?;
+                let stats = stream.consume_with_stats()?;
+                info!(
+                    "Consumed {} bytes, produced {} bytes",
+                    stats.consumed, stats.produced
+                );
+                if remove_input {
+                    debug!("Remove input file: {input:?}");
+                    std::fs::remove_file(input)?;
+                }
             } else {
                 let mut stream = generate_stdout_stream::<Duplicate>(input)?;
                 stream.consume()?;
             };
         }
         cli::Commands::Adler32 { input } => {
+            if args.deterministic {
+                assert_deterministic::<Adler32>(&input)?;
+            }
             let mut stream = generate_stdout_stream::<Adler32>(input)?;
             stream.consume()?;
         }
         cli::Commands::CRC32 { input } => {
+            if args.deterministic {
+                assert_deterministic::<CRC32>(&input)?;
+            }
             let mut stream = generate_stdout_stream::<CRC32>(input)?;
             stream.consume()?;
         }
@@ -1,15 +1,44 @@
-use sqsh::core::{Process, Stream};
+use sqsh::core::{Process, ProcessWriter, Stream};
+use sqsh::processors::CRC32;
 use std::{
     fs::File,
-    io::{BufReader, BufWriter, Stdout},
-    path::PathBuf,
+    io::{BufReader, BufWriter, Error, ErrorKind, Stdout},
+    path::{Path, PathBuf},
 };
 
+/// Whether both paths refer to the same existing file
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+/// Whether both paths refer to the same existing file
+#[cfg(not(unix))]
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
 /// Boilerplate for generating a stream from a file to a file
+///
+/// Fails if the output is the input file, which would be truncated before
+/// it is read.
 pub(crate) fn generate_file_stream<P: Process + Default>(
     input: PathBuf,
     output: PathBuf,
 ) -> std::io::Result<Stream<BufReader<File>, BufWriter<File>, P>> {
+    if same_file(&input, &output) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Input and output are the same file",
+        ));
+    }
     let i = File::open(input)?;
     let o = File::create(output)?;
     let bufreader = BufReader::new(i);
@@ -39,3 +68,25 @@ pub(crate) fn generate_stdout_stream<P: Process + Default>(
     let stream = sqsh::core::Stream::new(bufreader, writer, processor);
     Ok(stream)
 }
+
+/// Process the input twice and fail if the outputs differ
+///
+/// Only the CRC32 checksum of every output is kept, so the memory use does
+/// not grow with the size of the input.
+pub(crate) fn assert_deterministic<P: Process + Default>(input: &Path) -> std::io::Result<()> {
+    let digest = || -> std::io::Result<Vec<u8>> {
+        let bufreader = BufReader::new(File::open(input)?);
+        let mut crc = ProcessWriter::new(Vec::new(), CRC32::new());
+        let processor: P = Default::default();
+        sqsh::core::Stream::new(bufreader, &mut crc, processor).consume()?;
+        Ok(crc.finish()?)
+    };
+    if digest()? == digest()? {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            "Processing the same input twice produced different outputs",
+        ))
+    }
+}
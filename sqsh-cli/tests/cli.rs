@@ -0,0 +1,199 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Command running the compiled CLI binary
+fn sqsh() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_sqsh-cli"))
+}
+
+#[test]
+fn missing_input() {
+    let output = sqsh()
+        .args(["crc32", "/nonexistent/sqsh/input.raw"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("sqsh: I/O error:"), "{stderr}");
+}
+
+#[test]
+fn usage_error() {
+    // Reserved for clap, no runtime error of the CLI uses it
+    let output = sqsh().arg("unknown").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let output = sqsh().arg("crc32").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// Fresh temporary directory for a single test
+fn tempdir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sqsh-cli-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn keep_input() {
+    let dir = tempdir("keep");
+    let (input, output) = (dir.join("input.raw"), dir.join("output.raw"));
+    fs::write(&input, "Wikipedia").unwrap();
+    for args in [vec![], vec!["--keep"]] {
+        let status = sqsh()
+            .arg("duplicate")
+            .args([&input, &output])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(fs::read(&input).unwrap(), "Wikipedia".as_bytes());
+        assert_eq!(fs::read(&output).unwrap(), "Wikipedia".as_bytes());
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn remove_input() {
+    let dir = tempdir("remove");
+    let input = dir.join("input.raw");
+    fs::write(&input, "Wikipedia").unwrap();
+
+    let failing = dir.join("missing").join("output.raw");
+    let status = sqsh()
+        .arg("duplicate")
+        .args([&input, &failing])
+        .arg("--remove-input")
+        .output()
+        .unwrap()
+        .status;
+    assert!(!status.success());
+    assert!(input.exists());
+
+    let output = dir.join("output.raw");
+    let status = sqsh()
+        .arg("duplicate")
+        .args([&input, &output])
+        .arg("--remove-input")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(!input.exists());
+    assert_eq!(fs::read(&output).unwrap(), "Wikipedia".as_bytes());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn same_input_and_output() {
+    let dir = tempdir("same");
+    let input = dir.join("input.raw");
+    fs::write(&input, "Wikipedia").unwrap();
+    let link = dir.join("link.raw");
+    fs::hard_link(&input, &link).unwrap();
+    let relative = dir.join(".").join("input.raw");
+    for output in [&input, &link, &relative] {
+        for args in [vec![], vec!["--remove-input"]] {
+            let result = sqsh()
+                .arg("duplicate")
+                .args([&input, output])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(!result.status.success());
+            let stderr = String::from_utf8(result.stderr).unwrap();
+            assert!(stderr.contains("same file"), "{stderr}");
+            assert_eq!(fs::read(&input).unwrap(), "Wikipedia".as_bytes());
+        }
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn duplicate_byte_exact() {
+    let dir = tempdir("exact");
+    let large: Vec<u8> = (0..300_000u32).map(|i| (i * 31 % 251) as u8).collect();
+    let inputs: [&[u8]; 6] = [
+        b"",
+        b"no trailing newline",
+        b"trailing newline\n",
+        b"\n\n\r\n",
+        b"binary\0with\0\0NULs\xFF\x00",
+        &large,
+    ];
+    for (i, data) in inputs.iter().enumerate() {
+        let input = dir.join(format!("input{i}.raw"));
+        let output = dir.join(format!("output{i}.raw"));
+        fs::write(&input, data).unwrap();
+
+        let status = sqsh()
+            .arg("duplicate")
+            .args([&input, &output])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(
+            &fs::read(&output).unwrap(),
+            data,
+            "File output of input {i}"
+        );
+
+        let stdout = sqsh().arg("duplicate").arg(&input).output().unwrap();
+        assert!(stdout.status.success());
+        assert_eq!(&stdout.stdout, data, "Standard output of input {i}");
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn logs_stay_off_stdout() {
+    let dir = tempdir("logs");
+    let input = dir.join("input.raw");
+    fs::write(&input, "Wikipedia").unwrap();
+
+    let output = sqsh()
+        .args(["-vvvv", "duplicate"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, "Wikipedia".as_bytes());
+    assert!(!output.stderr.is_empty());
+
+    let output = sqsh()
+        .args(["-vvvv", "crc32"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, "CRC32<0xADAAC02E>".as_bytes());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn deterministic() {
+    let dir = tempdir("deterministic");
+    let input = dir.join("input.raw");
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i * 31 % 251) as u8).collect();
+    fs::write(&input, &data).unwrap();
+
+    let output = dir.join("output.raw");
+    let status = sqsh()
+        .args(["--deterministic", "duplicate"])
+        .args([&input, &output])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read(&output).unwrap(), data);
+
+    let plain = sqsh().arg("crc32").arg(&input).output().unwrap();
+    let checked = sqsh()
+        .args(["--deterministic", "crc32"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert!(checked.status.success());
+    assert_eq!(checked.stdout, plain.stdout);
+    assert_eq!(fs::read(&input).unwrap(), data);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,57 @@
+//! # Scientific pipeline
+//!
+//! Compresses a smooth series of `f64` samples with a pipeline of
+//! shuffle → delta → zigzag → Huffman coding and verifies the lossless
+//! roundtrip by checking the decoded data against the CRC32 checksum of the
+//! original.
+//!
+//! Run-length encoding is not part of the library yet, so the Huffman coder
+//! takes its place as the final stage.
+//!
+//! Run it with `cargo run --example scientific_pipeline`.
+use sqsh::core::Stream;
+use sqsh::processors::prelude::*;
+use sqsh::Result;
+
+/// Generate a smooth signal as little-endian `f64` bytes
+fn generate(samples: usize) -> Vec<u8> {
+    (0..samples)
+        .map(|i| (i as f64 / 100.0).sin() * 1000.0)
+        .map(|v| v.round() / 8.0)
+        .flat_map(f64::to_le_bytes)
+        .collect()
+}
+
+fn main() -> Result<()> {
+    let data = generate(100_000);
+    let mut crc = CRC32::new();
+    crc.process(&data, &mut Vec::new())?;
+    let expected = crc.checksum();
+
+    let builder = Pipeline::builder()
+        .add(ByteShuffle::new(8))
+        .add(DeltaEncoder::with_width(8))
+        .add(ZigZagEncoder::with_width(8))
+        .add(HuffmanEncoder::new());
+    let decoder = builder.decoder_pipeline();
+    let encoder = builder.build();
+
+    let mut encoded = Vec::new();
+    Stream::from_slice(&data, &mut encoded, encoder).consume()?;
+    let mut decoded = Vec::new();
+    Stream::from_slice(&encoded, &mut decoded, decoder).consume()?;
+
+    // Fails with `Error::ChecksumMismatch` if the roundtrip is not lossless
+    let checked = CheckedChecksum::<CRC32>::default().expect(expected);
+    Stream::from_slice(&decoded, &mut Vec::new(), checked).consume()?;
+
+    println!("Original:           {} bytes", data.len());
+    println!("Compressed:         {} bytes", encoded.len());
+    println!(
+        "Compression factor: {:.2}",
+        data.len() as f64 / encoded.len() as f64
+    );
+    println!("CRC32:              {expected:#010X} verified");
+    assert_eq!(data, decoded);
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! # Bits
+//!
+//! Helpers for writing and reading data at bit granularity. Bits are written
+//! MSB-first, i.e. the first bit written ends up in the most significant bit
+//! of the first byte.
+use alloc::vec::Vec;
+
+/// Writer packing values of arbitrary bit width into bytes
+#[derive(Debug, Default)]
+pub(crate) struct BitWriter {
+    buffer: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    /// Create a new bit writer
+    pub(crate) fn new() -> Self {
+        BitWriter { buffer: 0, bits: 0 }
+    }
+
+    /// Append the lowest `bits` bits of `value`, complete bytes go to the sink
+    ///
+    /// At most 56 bits can be written at once.
+    pub(crate) fn write(&mut self, value: u64, bits: u32, sink: &mut Vec<u8>) {
+        debug_assert!(bits <= 56, "At most 56 bits can be written at once");
+        if bits == 0 {
+            return;
+        }
+        let mask = (1u64 << bits) - 1;
+        self.buffer = (self.buffer << bits) | (value & mask);
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            sink.push((self.buffer >> self.bits) as u8);
+        }
+        self.buffer &= (1u64 << self.bits) - 1;
+    }
+
+    /// Number of bits not yet written to the sink
+    pub(crate) fn pending(&self) -> u32 {
+        self.bits
+    }
+
+    /// Write the pending bits padded with zeros to a complete byte
+    pub(crate) fn finish(&mut self, sink: &mut Vec<u8>) {
+        if self.bits > 0 {
+            sink.push((self.buffer << (8 - self.bits)) as u8);
+        }
+        self.reset();
+    }
+
+    /// Drop the pending bits
+    pub(crate) fn reset(&mut self) {
+        self.buffer = 0;
+        self.bits = 0;
+    }
+}
+
+/// Reader unpacking values of arbitrary bit width from bytes
+///
+/// Bytes are pushed as they arrive, so values may be split across calls.
+#[derive(Debug, Default)]
+pub(crate) struct BitReader {
+    buffer: u64,
+    bits: u32,
+}
+
+impl BitReader {
+    /// Create a new bit reader
+    pub(crate) fn new() -> Self {
+        BitReader { buffer: 0, bits: 0 }
+    }
+
+    /// Append a byte to the pending bits
+    ///
+    /// At most 56 bits can be pending before a byte is pushed.
+    pub(crate) fn push(&mut self, byte: u8) {
+        debug_assert!(self.bits <= 56, "Too many pending bits");
+        self.buffer = (self.buffer << 8) | byte as u64;
+        self.bits += 8;
+    }
+
+    /// Read the next `bits` bits, if enough bits are pending
+    pub(crate) fn read(&mut self, bits: u32) -> Option<u64> {
+        if bits > self.bits {
+            return None;
+        }
+        self.bits -= bits;
+        let value = (self.buffer >> self.bits) & ((1u64 << bits) - 1);
+        self.buffer &= (1u64 << self.bits) - 1;
+        Some(value)
+    }
+
+    /// Number of pending bits
+    pub(crate) fn pending(&self) -> u32 {
+        self.bits
+    }
+
+    /// Value of the pending bits
+    pub(crate) fn remainder(&self) -> u64 {
+        self.buffer
+    }
+
+    /// Drop the pending bits
+    pub(crate) fn reset(&mut self) {
+        self.buffer = 0;
+        self.bits = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_bits() {
+        let mut sink = Vec::new();
+        let mut writer = BitWriter::new();
+        writer.write(0b1, 1, &mut sink);
+        writer.write(0b0110, 4, &mut sink);
+        assert!(sink.is_empty());
+        writer.write(0xABCD, 16, &mut sink);
+        assert_eq!(sink, [0b1011_0101, 0b0101_1110]);
+        assert_eq!(writer.pending(), 5);
+        writer.finish(&mut sink);
+        assert_eq!(sink, [0b1011_0101, 0b0101_1110, 0b0110_1000]);
+        writer.finish(&mut sink);
+        assert_eq!(sink.len(), 3);
+    }
+
+    #[test]
+    fn read_bits() {
+        let mut reader = BitReader::new();
+        assert_eq!(reader.read(1), None);
+        reader.push(0b1011_0101);
+        assert_eq!(reader.read(1), Some(0b1));
+        assert_eq!(reader.read(4), Some(0b0110));
+        assert_eq!(reader.read(16), None);
+        reader.push(0b0101_1110);
+        reader.push(0b0110_1000);
+        assert_eq!(reader.read(16), Some(0xABCD));
+        assert_eq!((reader.pending(), reader.remainder()), (3, 0));
+    }
+}
@@ -3,6 +3,9 @@
 //! Checksums are used to check the integrity of the data after decompression.
 //! Each Checksum has to implement the `Process` trait.
 use super::Process;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::Display;
 
 /// Checksum trait for calculating the checksum from the internal state
 pub trait Checksum: Process {
@@ -12,6 +15,55 @@ pub trait Checksum: Process {
     fn checksum(&self) -> Self::Output;
 }
 
+/// Format in which a checksum writes its value to the sink in `finish`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumOutput {
+    /// The `Display` string, e.g. `CRC32<0xADAAC02E>`
+    #[default]
+    Text,
+    /// The raw value in big-endian byte order
+    RawBE,
+    /// The raw value in little-endian byte order
+    RawLE,
+}
+
+impl ChecksumOutput {
+    /// Write the value of `checksum` to the sink in this format
+    pub(crate) fn write<C>(&self, checksum: &C, sink: &mut Vec<u8>)
+    where
+        C: Checksum<Output = u32> + Display,
+    {
+        match self {
+            ChecksumOutput::Text => sink.extend(checksum.to_string().as_bytes()),
+            ChecksumOutput::RawBE => sink.extend(checksum.checksum().to_be_bytes()),
+            ChecksumOutput::RawLE => sink.extend(checksum.checksum().to_le_bytes()),
+        }
+    }
+}
+
+/// Error raised if a calculated checksum differs from the expected one
+///
+/// It is reported as `Error::ChecksumMismatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    /// Checksum the data was expected to have
+    pub expected: u64,
+    /// Checksum calculated from the data
+    pub calculated: u64,
+}
+
+impl Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (expected, calculated) = (self.expected, self.calculated);
+        write!(
+            f,
+            "Checksum mismatch: expected {expected:#X}, calculated {calculated:#X}"
+        )
+    }
+}
+
+impl core::error::Error for ChecksumMismatch {}
+
 #[cfg(test)]
 #[allow(dead_code)]
 pub(crate) mod tests {
@@ -0,0 +1,213 @@
+//! # Line framing
+//!
+//! This module compresses text line by line. Every line is processed
+//! independently into its own frame, so a single line can be decoded without
+//! decoding the lines before it. This is useful for append-only logs which
+//! should stay searchable after compression.
+//!
+//! Each frame consists of the length of the processed line as a LEB128
+//! variable-length integer followed by the processed bytes. Line breaks are
+//! part of the line they end, so a last line without a trailing line break
+//! is restored exactly.
+use crate::core::process::{Process, Reset};
+use crate::{Error, Result};
+use log::debug;
+use std::io::{BufRead, Write};
+
+/// Append `value` as LEB128 variable-length integer
+fn write_length(value: usize, sink: &mut Vec<u8>) {
+    let mut value = value;
+    while value >= 0x80 {
+        sink.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    sink.push(value as u8);
+}
+
+/// Read a LEB128 variable-length integer and return it with the bytes read
+fn read_length(data: &[u8]) -> Result<(usize, usize)> {
+    let mut value: usize = 0;
+    for (i, byte) in data.iter().enumerate() {
+        let shift = 7 * i as u32;
+        if shift >= usize::BITS {
+            break;
+        }
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(Error::invalid_code("Invalid frame length"))
+}
+
+/// LineFramedStream consumes the source line by line and writes one frame
+/// per line to the sink.
+///
+/// The processor is reset before each line.
+pub struct LineFramedStream<B, W, P> {
+    reader: B,
+    writer: W,
+    processor: P,
+}
+
+impl<B: BufRead, W: Write, P: Process + Reset> LineFramedStream<B, W, P> {
+    /// Create a new LineFramedStream object
+    pub fn new(reader: B, writer: W, processor: P) -> Self {
+        LineFramedStream {
+            reader,
+            writer,
+            processor,
+        }
+    }
+
+    /// Consume the source and return the number of written lines
+    pub fn consume(&mut self) -> Result<usize> {
+        let mut line = Vec::new();
+        let mut frame = Vec::new();
+        let mut buffer = Vec::new();
+        let mut lines = 0;
+        while self.reader.read_until(b'\n', &mut line)? > 0 {
+            self.processor.reset();
+            self.processor.process(&line, &mut buffer)?;
+            self.processor.finish(&mut buffer)?;
+            write_length(buffer.len(), &mut frame);
+            frame.append(&mut buffer);
+            self.writer.write_all(&frame)?;
+            frame.clear();
+            line.clear();
+            lines += 1;
+        }
+        self.writer.flush()?;
+        debug!("Wrote {lines} line frames");
+        Ok(lines)
+    }
+}
+
+/// Index over the frames written by a `LineFramedStream`
+pub struct LineFrames<'a> {
+    data: &'a [u8],
+    frames: Vec<(usize, usize)>,
+}
+
+impl<'a> LineFrames<'a> {
+    /// Index the frames of `data` without decoding them
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        let mut frames = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let (length, header) = read_length(&data[offset..])?;
+            let start = offset + header;
+            if length > data.len() - start {
+                return Err(Error::truncated("Truncated frame"));
+            }
+            frames.push((start, length));
+            offset = start + length;
+        }
+        Ok(LineFrames { data, frames })
+    }
+
+    /// Number of lines
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Whether there are no lines
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Decode the line with the given index
+    ///
+    /// Returns `None` if the index is out of range. Only the frame of the
+    /// requested line is passed to the decoder, which is reset beforehand.
+    pub fn decode_line<D: Process + Reset>(
+        &self,
+        index: usize,
+        decoder: &mut D,
+    ) -> Option<Result<Vec<u8>>> {
+        let (start, length) = *self.frames.get(index)?;
+        let mut line = Vec::new();
+        decoder.reset();
+        let result = decoder
+            .process(&self.data[start..start + length], &mut line)
+            .and_then(|_| decoder.finish(&mut line))
+            .map(|_| line);
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::{DeltaDecoder, DeltaEncoder};
+
+    /// Decoder counting the bytes handed to it
+    #[derive(Default)]
+    struct Counting {
+        inner: DeltaDecoder,
+        processed: usize,
+    }
+
+    impl Process for Counting {
+        fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+            self.processed += source.len();
+            self.inner.process(source, sink)
+        }
+        fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+            self.inner.finish(sink)
+        }
+    }
+
+    impl Reset for Counting {
+        fn reset(&mut self) {
+            self.inner.reset();
+        }
+    }
+
+    fn encode(log: &[u8]) -> (Vec<u8>, usize) {
+        let mut encoded = Vec::new();
+        let lines = LineFramedStream::new(log, &mut encoded, DeltaEncoder::new())
+            .consume()
+            .unwrap();
+        (encoded, lines)
+    }
+
+    #[test]
+    fn decode_middle_line() {
+        let long = "x".repeat(100_000);
+        let log = format!("12:00 start\n12:01 {long}\n\n12:02 middle\n12:03 no newline");
+        let (encoded, lines) = encode(log.as_bytes());
+        assert_eq!(lines, 5);
+
+        let frames = LineFrames::new(&encoded).unwrap();
+        assert_eq!(frames.len(), 5);
+        let mut decoder = Counting::default();
+        let line = frames.decode_line(3, &mut decoder).unwrap().unwrap();
+        assert_eq!(line, "12:02 middle\n".as_bytes());
+        assert_eq!(decoder.processed, line.len());
+
+        let decoded: Vec<u8> = (0..frames.len())
+            .flat_map(|i| frames.decode_line(i, &mut decoder).unwrap().unwrap())
+            .collect();
+        assert_eq!(decoded, log.as_bytes());
+        assert!(frames.decode_line(5, &mut decoder).is_none());
+    }
+
+    #[test]
+    fn frame_lengths() {
+        let mut sink = Vec::new();
+        for value in [0, 127, 128, 300, 100_000, usize::MAX] {
+            sink.clear();
+            write_length(value, &mut sink);
+            assert_eq!(read_length(&sink).unwrap(), (value, sink.len()));
+        }
+        assert!(read_length(&[0x80, 0x80]).is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        let (encoded, _) = encode("line one\nline two\n".as_bytes());
+        assert!(LineFrames::new(&encoded[..encoded.len() - 1]).is_err());
+        assert!(LineFrames::new(&[]).unwrap().is_empty());
+    }
+}
@@ -19,10 +19,34 @@
 //! These three components define the core of the data processing in the
 //! library. The interaction of these components are organised by a `Stream`
 //! object which coordinates the whole interaction.
+//!
+//! `Stream`, `ProcessWriter`, `LineFramedStream`, `MultiWriter` and the
+//! hash based `ProbTable` require the `std` feature.
+pub(crate) mod bits;
 pub(crate) mod checksum;
+#[cfg(feature = "std")]
+mod line;
+#[cfg(feature = "std")]
+mod multi_writer;
+mod pipeline;
+#[cfg(feature = "std")]
+mod probtable;
 pub(crate) mod process;
+#[cfg(feature = "std")]
 mod stream;
+#[cfg(feature = "std")]
+mod writer;
 
-pub use checksum::Checksum;
-pub use process::Process;
-pub use stream::Stream;
+pub use checksum::{Checksum, ChecksumMismatch, ChecksumOutput};
+#[cfg(feature = "std")]
+pub use line::{LineFramedStream, LineFrames};
+#[cfg(feature = "std")]
+pub use multi_writer::MultiWriter;
+pub use pipeline::{Pipeline, PipelineBuilder};
+#[cfg(feature = "std")]
+pub use probtable::ProbTable;
+pub use process::{Decoder, Encoder, Invertible, Process, Reset, Symbol};
+#[cfg(feature = "std")]
+pub use stream::{Stream, StreamStats};
+#[cfg(feature = "std")]
+pub use writer::ProcessWriter;
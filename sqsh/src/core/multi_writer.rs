@@ -0,0 +1,99 @@
+//! # Multi writer
+//!
+//! This module fans out a data stream to several sinks. Every write is
+//! forwarded to all inner writers, so a `Stream` can for example write its
+//! output to a file and a network connection at the same time.
+use std::io::{Result as IOResult, Write};
+
+/// Writer forwarding every write to a list of inner writers
+///
+/// The writers are written in order and the first error is returned
+/// immediately. The writers after the failing one do not receive the data.
+///
+/// ```
+/// use sqsh::core::{MultiWriter, Stream};
+/// use sqsh::processors::Duplicate;
+///
+/// let (mut a, mut b) = (Vec::new(), Vec::new());
+/// let writer = MultiWriter::new(vec![&mut a, &mut b]);
+/// Stream::from_slice("sqsh".as_bytes(), writer, Duplicate::new()).consume().unwrap();
+/// assert_eq!(a, b);
+/// ```
+pub struct MultiWriter<W> {
+    writers: Vec<W>,
+}
+
+impl<W: Write> MultiWriter<W> {
+    /// Create a new writer fanning out to `writers`
+    pub fn new(writers: Vec<W>) -> Self {
+        MultiWriter { writers }
+    }
+
+    /// Add another writer
+    pub fn push(&mut self, writer: W) {
+        self.writers.push(writer);
+    }
+
+    /// Return the inner writers
+    pub fn into_inner(self) -> Vec<W> {
+        self.writers
+    }
+}
+
+impl<W: Write> Write for MultiWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        for writer in self.writers.iter_mut() {
+            writer.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> IOResult<()> {
+        for writer in self.writers.iter_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Stream;
+    use crate::processors::Duplicate;
+    use std::io::{Error, ErrorKind};
+
+    /// Writer failing on every write
+    struct Failing;
+
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> IOResult<usize> {
+            Err(Error::new(ErrorKind::BrokenPipe, "Closed"))
+        }
+        fn flush(&mut self) -> IOResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fan_out() {
+        let source = "Awesome-string-baby".repeat(1000).into_bytes();
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        let writer = MultiWriter::new(vec![&mut a, &mut b]);
+        Stream::from_slice(&source, writer, Duplicate::new())
+            .consume()
+            .unwrap();
+        assert_eq!(a, source);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn fail_fast() {
+        let mut sink = Vec::new();
+        let writers: Vec<Box<dyn Write>> = vec![Box::new(Failing), Box::new(&mut sink)];
+        let mut writer = MultiWriter::new(writers);
+        let err = writer.write_all("sqsh".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+        drop(writer);
+        assert!(sink.is_empty());
+    }
+}
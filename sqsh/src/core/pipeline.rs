@@ -0,0 +1,169 @@
+//! # Pipeline
+//!
+//! This module composes several processors into a single processor. The
+//! output of each stage is the input of the following stage. Pipelines are
+//! created with a fluent builder, which also derives the decoding pipeline
+//! applying the inverse stages in reverse order.
+//!
+//! ```
+//! use sqsh::core::{Pipeline, Process};
+//! use sqsh::processors::{Duplicate, RefDeltaEncoder, ShrinkOrBypass};
+//!
+//! let builder = Pipeline::builder()
+//!     .add(RefDeltaEncoder::with_reference(&[1, 2], 2))
+//!     .add(ShrinkOrBypass::new(Duplicate::new()));
+//! let mut decoder = builder.decoder_pipeline();
+//! let mut encoder = builder.build();
+//!
+//! let mut encoded = Vec::new();
+//! encoder.process("sqsh".as_bytes(), &mut encoded).unwrap();
+//! encoder.finish(&mut encoded).unwrap();
+//!
+//! let mut decoded = Vec::new();
+//! decoder.process(&encoded, &mut decoded).unwrap();
+//! decoder.finish(&mut decoded).unwrap();
+//! assert_eq!(decoded, "sqsh".as_bytes());
+//! ```
+use crate::core::process::{Invertible, Process};
+use crate::Result;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use log::debug;
+
+/// Object safe stage of a pipeline which is able to create its inverse
+trait Stage: Process {
+    fn inverse_stage(&self) -> Box<dyn Process>;
+}
+
+impl<E: Invertible + 'static> Stage for E {
+    fn inverse_stage(&self) -> Box<dyn Process> {
+        Box::new(self.inverse())
+    }
+}
+
+/// Sequence of processors acting as a single processor
+pub struct Pipeline {
+    stages: Vec<Box<dyn Process>>,
+}
+
+impl Pipeline {
+    /// Start building a new pipeline
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder { stages: Vec::new() }
+    }
+
+    /// Number of stages in the pipeline
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Whether the pipeline has no stages and simply copies the data
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+impl Process for Pipeline {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        let mut current = source.to_vec();
+        for stage in self.stages.iter_mut() {
+            let mut next = Vec::new();
+            stage.process(&current, &mut next)?;
+            current = next;
+        }
+        sink.append(&mut current);
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        // The trailing output of each stage has to pass all later stages
+        let mut current = Vec::new();
+        for stage in self.stages.iter_mut() {
+            let mut next = Vec::new();
+            stage.process(&current, &mut next)?;
+            stage.finish(&mut next)?;
+            current = next;
+        }
+        sink.append(&mut current);
+        Ok(0)
+    }
+    fn flush(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        let mut current = Vec::new();
+        for stage in self.stages.iter_mut() {
+            let mut next = Vec::new();
+            stage.process(&current, &mut next)?;
+            stage.flush(&mut next)?;
+            current = next;
+        }
+        sink.append(&mut current);
+        Ok(0)
+    }
+}
+
+/// Fluent builder for `Pipeline`
+pub struct PipelineBuilder {
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl PipelineBuilder {
+    /// Append an encoder as the next stage
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<E: Invertible + 'static>(mut self, encoder: E) -> Self {
+        self.stages.push(Box::new(encoder));
+        self
+    }
+
+    /// Build the pipeline inverting this pipeline
+    pub fn decoder_pipeline(&self) -> Pipeline {
+        debug!("Build decoder pipeline with {} stages", self.stages.len());
+        let stages = self
+            .stages
+            .iter()
+            .rev()
+            .map(|s| s.inverse_stage())
+            .collect();
+        Pipeline { stages }
+    }
+
+    /// Build the encoding pipeline
+    pub fn build(self) -> Pipeline {
+        debug!("Build pipeline with {} stages", self.stages.len());
+        let stages = self
+            .stages
+            .into_iter()
+            .map(|s| s as Box<dyn Process>)
+            .collect();
+        Pipeline { stages }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::process_chunked;
+    use crate::processors::{Duplicate, RefDeltaEncoder, ShrinkOrBypass};
+
+    #[test]
+    fn roundtrip() {
+        let source = "Awesome-string-baby".repeat(20).into_bytes();
+        let builder = Pipeline::builder()
+            .add(RefDeltaEncoder::with_reference(&[3, 1, 4, 1], 4))
+            .add(Duplicate::new())
+            .add(ShrinkOrBypass::new(Duplicate::new()));
+        let mut decoder = builder.decoder_pipeline();
+        let mut encoder = builder.build();
+        assert_eq!(encoder.len(), 3);
+        assert_eq!(decoder.len(), 3);
+        for chunk_size in [1, 5, 4096] {
+            let encoded = process_chunked(&mut encoder, &source, chunk_size);
+            assert_eq!(encoded.len(), source.len() + 1);
+            assert_eq!(process_chunked(&mut decoder, &encoded, chunk_size), source);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let mut pipeline = Pipeline::builder().build();
+        assert!(pipeline.is_empty());
+        assert_eq!(process_chunked(&mut pipeline, b"sqsh", 2), b"sqsh");
+    }
+}
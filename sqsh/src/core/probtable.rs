@@ -0,0 +1,515 @@
+//! # Probability table
+//!
+//! Frequency table of symbols ordered by their count. The table assigns each
+//! symbol a rank: the most frequent symbol has rank `0`, the second most
+//! frequent rank `1`, and so on. The order of symbols with equal counts only
+//! depends on the sequence of inserts, so the ranks are reproducible.
+//!
+//! The table is the shared model of the entropy coders in this crate.
+use std::collections::HashMap;
+use std::hash::Hash;
+
+fn check_factor(factor: f64) {
+    assert!(
+        (0.0..=1.0).contains(&factor),
+        "Decay factor must be between 0 and 1"
+    );
+}
+
+/// Symbol of the table together with its count
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    value: T,
+    count: usize,
+}
+
+/// Frequency table of symbols ordered by descending count
+///
+/// ```
+/// use sqsh::core::ProbTable;
+///
+/// let mut table = ProbTable::new();
+/// table.feed("abracadabra".bytes());
+/// assert_eq!(table.rank(&b'a'), Some(0));
+/// assert_eq!(table.count(&b'b'), Some(2));
+/// assert_eq!(table.iter().collect::<Vec<_>>(), [&b'a', &b'b', &b'r', &b'c', &b'd']);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProbTable<T> {
+    /// Position of every symbol in `sorted_vec`
+    hm: HashMap<T, usize>,
+    /// Symbols ordered by descending count
+    sorted_vec: Vec<Entry<T>>,
+    /// Sum of all counts
+    total: usize,
+    /// Maximum number of distinct symbols, unbounded if `None`
+    max_members: Option<usize>,
+    /// Number of evicted symbols
+    evictions: usize,
+    /// Number of inserts between decays and the decay factor
+    decay_interval: Option<(usize, f64)>,
+    /// Number of inserts since the last decay
+    inserts: usize,
+}
+
+impl<T: Hash + Eq + Clone> ProbTable<T> {
+    /// Create a new empty table
+    pub fn new() -> Self {
+        ProbTable {
+            hm: HashMap::new(),
+            sorted_vec: Vec::new(),
+            total: 0,
+            max_members: None,
+            evictions: 0,
+            decay_interval: None,
+            inserts: 0,
+        }
+    }
+
+    /// Create a new empty table holding at most `max_members` symbols
+    ///
+    /// Inserting an unknown symbol into a full table first evicts the symbol
+    /// with the lowest rank, the last of the lowest count group. Its count is
+    /// removed from the total, so the table stays coherent.
+    ///
+    /// ```
+    /// use sqsh::core::ProbTable;
+    ///
+    /// let mut table = ProbTable::with_max_members(2);
+    /// table.feed("aab".bytes());
+    /// table.insert(b'c');
+    /// assert_eq!(table.rank(&b'b'), None);
+    /// assert_eq!(table.rank(&b'c'), Some(1));
+    /// assert_eq!(table.evictions(), 1);
+    /// ```
+    ///
+    /// Panics if `max_members` is zero.
+    pub fn with_max_members(max_members: usize) -> Self {
+        assert!(max_members > 0, "Table must hold at least one symbol");
+        ProbTable {
+            max_members: Some(max_members),
+            ..Self::new()
+        }
+    }
+
+    /// Increment the count of `val` by one
+    ///
+    /// Unknown symbols are added with a count of one.
+    pub fn insert(&mut self, val: T) {
+        self.total += 1;
+        let position = match self.hm.get(&val) {
+            Some(&position) => {
+                self.sorted_vec[position].count += 1;
+                position
+            }
+            None => {
+                if self.max_members == Some(self.sorted_vec.len()) {
+                    self.evict();
+                }
+                self.hm.insert(val.clone(), self.sorted_vec.len());
+                self.sorted_vec.push(Entry {
+                    value: val,
+                    count: 1,
+                });
+                self.sorted_vec.len() - 1
+            }
+        };
+        let target = self.renormalize(position);
+        debug_assert!(self.is_coherent_at(target) && self.is_coherent_at(position));
+        if let Some((interval, factor)) = self.decay_interval {
+            self.inserts += 1;
+            if self.inserts == interval {
+                self.inserts = 0;
+                self.decay(factor);
+            }
+        }
+    }
+
+    /// Create a new empty table decaying its counts by `factor` after every
+    /// `interval` inserts
+    ///
+    /// Recent symbols dominate the ranks of such a table, which suits
+    /// non-stationary data. See `decay` for the rounding.
+    ///
+    /// Panics if `interval` is zero or `factor` is not between 0 and 1.
+    pub fn with_decay_interval(interval: usize, factor: f64) -> Self {
+        assert!(interval > 0, "Decay interval must be positive");
+        check_factor(factor);
+        ProbTable {
+            decay_interval: Some((interval, factor)),
+            ..Self::new()
+        }
+    }
+
+    /// Multiply every count by `factor`
+    ///
+    /// The counts are rounded to the nearest integer and symbols with a
+    /// count of zero are removed. Symbols of equal counts keep their order,
+    /// so the ranks stay reproducible.
+    ///
+    /// ```
+    /// use sqsh::core::ProbTable;
+    ///
+    /// let mut table = ProbTable::new();
+    /// table.feed("aaaabbc".bytes());
+    /// table.decay(0.3);
+    /// assert_eq!(table.count(&b'a'), Some(1));
+    /// assert_eq!(table.count(&b'b'), Some(1));
+    /// assert_eq!(table.count(&b'c'), None);
+    /// assert_eq!(table.total(), 2);
+    /// ```
+    ///
+    /// Panics if `factor` is not between 0 and 1.
+    pub fn decay(&mut self, factor: f64) {
+        check_factor(factor);
+        for entry in self.sorted_vec.iter_mut() {
+            entry.count = (entry.count as f64 * factor).round() as usize;
+        }
+        self.sorted_vec.retain(|e| e.count > 0);
+        // Stable, so the order of equal counts is kept
+        self.sorted_vec.sort_by_key(|e| std::cmp::Reverse(e.count));
+        self.hm.clear();
+        for (i, entry) in self.sorted_vec.iter().enumerate() {
+            self.hm.insert(entry.value.clone(), i);
+        }
+        self.total = self.sorted_vec.iter().map(|e| e.count).sum();
+        debug_assert!(self.is_coherent());
+    }
+
+    /// Remove the symbol with the lowest rank
+    fn evict(&mut self) {
+        let entry = self.sorted_vec.pop().expect("Full table is not empty");
+        self.hm.remove(&entry.value);
+        self.total -= entry.count;
+        self.evictions += 1;
+    }
+
+    /// Insert every symbol of `values`
+    pub fn feed<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        values.into_iter().for_each(|v| self.insert(v));
+    }
+
+    /// Restore the order after the count at `position` was incremented
+    ///
+    /// The entry is swapped with the first entry of its previous count
+    /// group, so it is placed in front of all entries sharing its old count.
+    ///
+    /// The entries before `position` are sorted, so the start of the group
+    /// is found by a binary search in O(log n).
+    ///
+    /// Returns the new position of the entry.
+    fn renormalize(&mut self, position: usize) -> usize {
+        let count = self.sorted_vec[position].count;
+        let target = self.sorted_vec[..position].partition_point(|e| e.count >= count);
+        if target != position {
+            self.sorted_vec.swap(target, position);
+            self.hm
+                .insert(self.sorted_vec[target].value.clone(), target);
+            self.hm
+                .insert(self.sorted_vec[position].value.clone(), position);
+        }
+        target
+    }
+
+    /// Rank of `val`, `0` being the most frequent symbol
+    pub fn rank(&self, val: &T) -> Option<usize> {
+        self.hm.get(val).copied()
+    }
+
+    /// Symbol with the given rank
+    pub fn position(&self, rank: usize) -> Option<&T> {
+        self.sorted_vec.get(rank).map(|e| &e.value)
+    }
+
+    /// Number of times `val` was inserted
+    pub fn count(&self, val: &T) -> Option<usize> {
+        self.rank(val).map(|r| self.sorted_vec[r].count)
+    }
+
+    /// Sum of the counts of all symbols
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Cumulative counts `(low, high)` of `val` in rank order
+    ///
+    /// `low` is the sum of the counts of all symbols ranked before `val` and
+    /// `high - low` is the count of `val`.
+    pub fn cumulative_frequency(&self, val: &T) -> Option<(usize, usize)> {
+        let rank = self.rank(val)?;
+        let low: usize = self.sorted_vec[..rank].iter().map(|e| e.count).sum();
+        Some((low, low + self.sorted_vec[rank].count))
+    }
+
+    /// Relative frequency of `val`, its count divided by the total
+    pub fn probability(&self, val: &T) -> Option<f64> {
+        self.count(val).map(|c| c as f64 / self.total as f64)
+    }
+
+    /// Draw a symbol with a probability proportional to its count
+    ///
+    /// `unit` is a uniformly distributed value in `[0, 1)` of any random
+    /// number generator, e.g. `rng.gen::<f64>()` of the `rand` crate.
+    /// Values outside the interval are clamped. Returns `None` for an empty
+    /// table.
+    ///
+    /// The symbol is found by a cumulative scan in rank order without a
+    /// cache of the cumulative counts, so a draw takes O(members) time.
+    ///
+    /// ```
+    /// use sqsh::core::ProbTable;
+    ///
+    /// let table: ProbTable<u8> = b"aaab".iter().copied().collect();
+    /// assert_eq!(table.sample(0.5), Some(&b'a'));
+    /// assert_eq!(table.sample(0.8), Some(&b'b'));
+    /// ```
+    pub fn sample(&self, unit: f64) -> Option<&T> {
+        let last = self.sorted_vec.last()?;
+        let target = (unit.clamp(0.0, 1.0) * self.total as f64) as usize;
+        let mut high = 0;
+        let entry = self
+            .sorted_vec
+            .iter()
+            .find(|e| {
+                high += e.count;
+                target < high
+            })
+            .unwrap_or(last);
+        Some(&entry.value)
+    }
+
+    /// Number of distinct symbols in the table
+    pub fn members(&self) -> usize {
+        self.sorted_vec.len()
+    }
+
+    /// Number of symbols evicted from a table with a maximum size
+    pub fn evictions(&self) -> usize {
+        self.evictions
+    }
+
+    /// Whether the table contains no symbols
+    pub fn is_empty(&self) -> bool {
+        self.sorted_vec.is_empty()
+    }
+
+    /// Iterate over the symbols in rank order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.sorted_vec.iter().map(|e| &e.value)
+    }
+
+    /// The `n` most frequent symbols with their counts in rank order
+    ///
+    /// Returns all symbols if the table holds fewer than `n`.
+    ///
+    /// ```
+    /// use sqsh::core::ProbTable;
+    ///
+    /// let mut table = ProbTable::new();
+    /// table.feed("abracadabra".bytes());
+    /// assert_eq!(table.most_common(3), [(b'a', 5), (b'b', 2), (b'r', 2)]);
+    /// ```
+    pub fn most_common(&self, n: usize) -> Vec<(T, usize)> {
+        self.sorted_vec
+            .iter()
+            .take(n)
+            .map(|e| (e.value.clone(), e.count))
+            .collect()
+    }
+
+    /// Whether the entry at `position` agrees with the map and its neighbours
+    ///
+    /// Cheap check of the entries touched by a single insert, the complete
+    /// check is `is_coherent`.
+    fn is_coherent_at(&self, position: usize) -> bool {
+        let entry = &self.sorted_vec[position];
+        self.hm.get(&entry.value) == Some(&position)
+            && (position == 0 || self.sorted_vec[position - 1].count >= entry.count)
+            && self
+                .sorted_vec
+                .get(position + 1)
+                .is_none_or(|next| entry.count >= next.count)
+    }
+
+    /// Whether the map and the sorted entries agree with each other
+    pub fn is_coherent(&self) -> bool {
+        self.hm.len() == self.sorted_vec.len()
+            && self
+                .sorted_vec
+                .iter()
+                .enumerate()
+                .all(|(i, e)| self.hm.get(&e.value) == Some(&i))
+            && self.sorted_vec.windows(2).all(|w| w[0].count >= w[1].count)
+            && self.sorted_vec.iter().map(|e| e.count).sum::<usize>() == self.total
+    }
+}
+
+impl<T: Hash + Eq + Clone> Default for ProbTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Collect symbols into a new table
+///
+/// ```
+/// use sqsh::core::ProbTable;
+///
+/// let table: ProbTable<u8> = b"abracadabra".iter().copied().collect();
+/// assert_eq!(table.rank(&b'a'), Some(0));
+/// assert_eq!(table.total(), 11);
+/// ```
+impl<T: Hash + Eq + Clone> FromIterator<T> for ProbTable<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut table = ProbTable::new();
+        table.feed(iter);
+        table
+    }
+}
+
+/// Insert every symbol, like `feed`
+impl<T: Hash + Eq + Clone> Extend<T> for ProbTable<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.feed(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks() {
+        let mut table = ProbTable::new();
+        table.feed([3u8, 1, 2, 2, 1, 2]);
+        assert_eq!(table.members(), 3);
+        assert_eq!(table.rank(&2), Some(0));
+        assert_eq!(table.rank(&1), Some(1));
+        assert_eq!(table.rank(&3), Some(2));
+        assert_eq!(table.position(0), Some(&2));
+        assert_eq!(table.count(&2), Some(3));
+        assert_eq!(table.rank(&4), None);
+        assert!(table.is_coherent());
+    }
+
+    #[test]
+    fn equal_counts() {
+        let mut table = ProbTable::new();
+        table.feed(0..=u8::MAX);
+        assert!(table.iter().copied().eq(0..=u8::MAX));
+        // The incremented symbol moves to the front of its old count group
+        table.insert(200);
+        table.insert(100);
+        assert_eq!(table.rank(&200), Some(0));
+        assert_eq!(table.rank(&100), Some(1));
+        // The displaced group members take over the vacated positions
+        assert_eq!(table.rank(&0), Some(200));
+        assert_eq!(table.rank(&1), Some(100));
+        assert!(table.is_coherent());
+    }
+
+    #[test]
+    fn skewed_inserts() {
+        let mut table: ProbTable<u8> = (0..=u8::MAX).collect();
+        for i in 0..20_000usize {
+            table.insert((i * i % 251 % (i % 13 + 1)) as u8);
+        }
+        assert!(table.is_coherent());
+        assert_eq!(table.position(0), Some(&0));
+    }
+
+    #[test]
+    fn cumulative_frequency() {
+        let mut table = ProbTable::new();
+        table.feed("abracadabra".bytes());
+        assert_eq!(table.total(), 11);
+        assert_eq!(table.cumulative_frequency(&b'a'), Some((0, 5)));
+        assert_eq!(table.cumulative_frequency(&b'b'), Some((5, 7)));
+        assert_eq!(table.cumulative_frequency(&b'd'), Some((10, 11)));
+        assert_eq!(table.cumulative_frequency(&b'z'), None);
+    }
+
+    #[test]
+    fn max_members() {
+        let mut table = ProbTable::with_max_members(16);
+        for i in 0..5000u32 {
+            table.insert(i % 7);
+            table.insert(1000 + i);
+            assert!(table.members() <= 16);
+        }
+        assert!(table.is_coherent());
+        assert_eq!(table.evictions(), 5000 - 9);
+        // The frequent symbols survive the churn of unique symbols
+        assert!((0..7).all(|v| table.rank(&v).is_some_and(|r| r < 7)));
+        assert_eq!(table.members(), 16);
+        assert_eq!(ProbTable::<u8>::new().evictions(), 0);
+    }
+
+    #[test]
+    fn decay() {
+        let mut table = ProbTable::new();
+        table.feed((0..100u8).flat_map(|v| std::iter::repeat_n(v, v as usize + 1)));
+        table.decay(0.1);
+        assert!(table.is_coherent());
+        // Counts below 5 round to zero
+        assert_eq!(table.members(), 96);
+        assert_eq!(table.count(&99), Some(10));
+        assert_eq!(table.count(&4), Some(1));
+        assert_eq!(table.rank(&3), None);
+        table.decay(0.0);
+        assert!(table.is_empty() && table.is_coherent());
+    }
+
+    #[test]
+    fn drift() {
+        let source = std::iter::repeat_n(b'a', 1000).chain(std::iter::repeat_n(b'b', 300));
+        let mut table = ProbTable::new();
+        table.feed(source.clone());
+        assert_eq!(table.position(0), Some(&b'a'));
+        // The early symbol is forgotten by a decaying table
+        let mut table = ProbTable::with_decay_interval(100, 0.5);
+        table.feed(source);
+        assert_eq!(table.position(0), Some(&b'b'));
+        assert!(table.is_coherent());
+    }
+
+    #[test]
+    fn collect() {
+        let mut table: ProbTable<u8> = [3, 1, 2, 2, 1, 2].into_iter().collect();
+        let mut fed = ProbTable::new();
+        fed.feed([3, 1, 2, 2, 1, 2]);
+        assert!(table.iter().eq(fed.iter()));
+        table.extend([3, 3, 3]);
+        assert_eq!(table.rank(&3), Some(0));
+        assert_eq!(table.total(), 9);
+        assert!(table.is_coherent());
+    }
+
+    #[test]
+    fn sample() {
+        let table: ProbTable<u8> = "abracadabra".bytes().collect();
+        assert_eq!(table.probability(&b'a'), Some(5.0 / 11.0));
+        assert_eq!(table.probability(&b'z'), None);
+        let mut drawn = ProbTable::new();
+        for i in 0..1100 {
+            drawn.insert(*table.sample(i as f64 / 1100.0).unwrap());
+        }
+        // Evenly spaced draws reproduce the counts exactly
+        assert!(table
+            .iter()
+            .all(|v| drawn.count(v) == table.count(v).map(|c| c * 100)));
+        assert_eq!(table.sample(1.0), Some(&b'd'));
+        assert_eq!(table.sample(-1.0), Some(&b'a'));
+    }
+
+    #[test]
+    fn empty() {
+        let table = ProbTable::<u8>::default();
+        assert!(table.is_empty());
+        assert_eq!(table.position(0), None);
+        assert_eq!(table.total(), 0);
+        assert!(table.most_common(3).is_empty());
+        assert_eq!(table.sample(0.5), None);
+        assert!(table.is_coherent());
+    }
+}
@@ -3,27 +3,176 @@
 //! This module defines the processing unit of the crate. It abstracts the
 //! read and write of the data streams. It is the most integral part in the
 //! library and shared by all components.
-use std::io::Result as IOResult;
+//!
+//! Processors work on symbols, which are bytes by default. Processors of
+//! wider symbols implement `Process<S>` for their symbol type `S` and are
+//! driven directly, as `Stream` reads and writes bytes.
+use crate::Result;
+use alloc::vec::Vec;
 
-/// The `Process` trait allows processing bytes from a source and
+/// The `Process` trait allows processing symbols from a source and
 /// writing the results to a sink.
 ///
 /// Implementor of the `Process` trait are called `processors`.
 ///
-/// This is an abstraction of any computational process. The bytes from the
-/// source will be read. The processor decides based on the read bytes what to
-/// write to the sink. It returns the number of bytes processed.
-pub trait Process {
+/// This is an abstraction of any computational process. The symbols from the
+/// source will be read. The processor decides based on the read symbols what
+/// to write to the sink. It returns the number of symbols processed. The
+/// symbol type defaults to `u8`, so `Process` is a byte processor.
+pub trait Process<S = u8> {
     /// Process the data from the source and write output to the sink
-    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> IOResult<usize>;
+    fn process(&mut self, source: &[S], sink: &mut Vec<S>) -> Result<usize>;
     /// Finish the processing by outputing possible further data
-    fn finish(&mut self, sink: &mut Vec<u8>) -> IOResult<usize>;
+    ///
+    /// Calling `finish` again without processing further data in between
+    /// writes nothing to the sink.
+    fn finish(&mut self, sink: &mut Vec<S>) -> Result<usize>;
+    /// Output all pending data without ending the stream
+    ///
+    /// In contrast to `finish` the state of the processor is kept and the
+    /// processing continues afterwards. The output up to this point can be
+    /// decoded on its own. Processors which cannot emit pending data before
+    /// the end of the stream keep the default implementation, which does
+    /// nothing.
+    fn flush(&mut self, _sink: &mut Vec<S>) -> Result<usize> {
+        Ok(0)
+    }
+    /// Upper bound of the symbols written by processing `input_len` symbols
+    ///
+    /// Callers may use the bound to reserve space in the sink up front.
+    /// The default `None` means the bound is unknown.
+    fn max_output_size(&self, _input_len: usize) -> Option<usize> {
+        None
+    }
+}
+
+/// Integer symbols with wrapping arithmetic
+///
+/// Implemented for the unsigned and signed integers of 8 to 64 bits.
+pub trait Symbol: Copy + Default + PartialEq + core::fmt::Debug {
+    /// Sum wrapping around at the bounds of the type
+    fn wrapping_add(self, other: Self) -> Self;
+    /// Difference wrapping around at the bounds of the type
+    fn wrapping_sub(self, other: Self) -> Self;
+}
+
+macro_rules! impl_symbol {
+    ($($t:ty),*) => {
+        $(impl Symbol for $t {
+            fn wrapping_add(self, other: Self) -> Self {
+                <$t>::wrapping_add(self, other)
+            }
+            fn wrapping_sub(self, other: Self) -> Self {
+                <$t>::wrapping_sub(self, other)
+            }
+        })*
+    };
+}
+
+impl_symbol!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Marker trait for processors encoding the source.
+pub trait Encoder<S = u8>: Process<S> {}
+
+/// Marker trait for processors decoding the output of `Self::Encoder`.
+///
+/// The associated type pairs each decoder with its encoder, so that generic
+/// code can rely on the compiler to match both directions.
+///
+/// ```
+/// use sqsh::core::{Decoder, Encoder};
+/// use sqsh::processors::{Duplicate, ShrinkOrBypass, ShrinkOrBypassDecoder};
+///
+/// fn pair<E: Encoder, D: Decoder<Encoder = E>>() {}
+/// pair::<ShrinkOrBypass<Duplicate>, ShrinkOrBypassDecoder<Duplicate>>();
+/// ```
+pub trait Decoder<S = u8>: Process<S> {
+    type Encoder: Encoder<S>;
+}
+
+/// Encoders which are able to create the decoder inverting them.
+pub trait Invertible<S = u8>: Encoder<S> {
+    type Decoder: Decoder<S, Encoder = Self>;
+
+    /// Create a decoder matching the configuration of this encoder
+    fn inverse(&self) -> Self::Decoder;
+}
+
+/// The `Reset` trait allows reusing a processor for independent data.
+///
+/// After a reset the processor behaves as if it was newly constructed while
+/// keeping its configuration and allocated memory.
+pub trait Reset {
+    /// Reset the inner state of the processor
+    fn reset(&mut self);
 }
 
 #[cfg(test)]
 #[allow(dead_code)]
 pub(crate) mod tests {
-    use super::Process;
+    use super::{Invertible, Process};
+    use crate::Result;
+
+    /// Processor holding back all data until `flush` or `finish`
+    #[derive(Default)]
+    pub(crate) struct HoldBack {
+        pending: Vec<u8>,
+    }
+
+    impl Process for HoldBack {
+        fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> Result<usize> {
+            self.pending.extend(source);
+            Ok(source.len())
+        }
+        fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+            self.flush(sink)
+        }
+        fn flush(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+            sink.append(&mut self.pending);
+            Ok(0)
+        }
+    }
+
+    /// Process the source in chunks of `chunk_size` symbols
+    pub(crate) fn process_chunked<S, P: Process<S>>(
+        processor: &mut P,
+        source: &[S],
+        chunk_size: usize,
+    ) -> Vec<S> {
+        let mut sink = Vec::new();
+        for chunk in source.chunks(chunk_size) {
+            processor.process(chunk, &mut sink).expect("Error");
+        }
+        processor.finish(&mut sink).expect("Error");
+        sink
+    }
+
+    /// Encode and decode the source in various chunk sizes and return the
+    /// encoded data
+    pub(crate) fn test_roundtrip<S: PartialEq + std::fmt::Debug, E: Invertible<S>>(
+        mut encoder: E,
+        source: &[S],
+    ) -> Vec<S> {
+        let mut decoder = encoder.inverse();
+        let encoded = process_chunked(&mut encoder, source, 4096);
+        for chunk_size in [1, 3, 7, 4096] {
+            assert_eq!(process_chunked(&mut encoder, source, chunk_size), encoded);
+            assert_eq!(process_chunked(&mut decoder, &encoded, chunk_size), source);
+        }
+        encoded
+    }
+
+    /// Process and finish the source, then check that a second `finish`
+    /// emits nothing
+    pub(crate) fn test_finish_twice<S: std::fmt::Debug, P: Process<S>>(
+        mut processor: P,
+        source: &[S],
+    ) {
+        process_chunked(&mut processor, source, 4096);
+        let mut sink = Vec::new();
+        processor.finish(&mut sink).expect("Error");
+        assert!(sink.is_empty(), "Second finish emitted {sink:?}");
+    }
 
     pub(crate) fn test_buffered_process<P: Process + Default>(source: &[u8], expected: &[u8]) {
         let mut d: P = Default::default();
@@ -34,4 +183,24 @@ pub(crate) mod tests {
         result.append(&mut fin);
         assert_eq!(result, expected)
     }
+
+    /// Varied source spanning two 4 KiB chunks of a `Stream`
+    pub(crate) fn multi_chunk_source() -> Vec<u8> {
+        (0..6000u32).map(|i| (i * i / 7 % 251) as u8).collect()
+    }
+
+    /// Process the source with fresh processors in various chunk sizes and
+    /// check that all outputs are identical
+    pub(crate) fn test_deterministic_process<P: Process + Default>(source: &[u8]) {
+        test_deterministic_with(P::default, source)
+    }
+
+    /// Like `test_deterministic_process` for processors created by `new`
+    pub(crate) fn test_deterministic_with<P: Process>(new: impl Fn() -> P, source: &[u8]) {
+        let expected = process_chunked(&mut new(), source, 4096);
+        for chunk_size in [1, 7, 4096, source.len().max(1)] {
+            let output = process_chunked(&mut new(), source, chunk_size);
+            assert_eq!(output, expected, "Chunk size {chunk_size}");
+        }
+    }
 }
@@ -4,17 +4,39 @@
 //! It sets up the data stream and the consumers of the data and abstracts
 //! the actual source, sink and processor inbetween.
 use crate::core::process::Process;
-use std::io::{BufRead, Result as IOResult, Write};
+use crate::Result;
+use std::io::{BufRead, Write};
 
 /// Default buffer size for the write buffer
 const WRITE_BUFFER_SIZE: usize = 4_096;
 
+/// Number of bytes moved by a `Stream`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// Bytes consumed from the source
+    pub consumed: usize,
+    /// Bytes written to the sink
+    pub produced: usize,
+}
+
+impl StreamStats {
+    /// Ratio of consumed to produced bytes, the compression factor when
+    /// encoding
+    ///
+    /// Returns `None` if nothing was produced.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.produced > 0).then(|| self.consumed as f64 / self.produced as f64)
+    }
+}
+
 /// Stream consumes the source and writes the output of the
 /// processor to the sink.
 ///
 /// The main task of the `Stream` is to consume the source. The only deciding
 /// property is the buffer size. After that no property is being changed. The
-/// `consume` method **fully** consumes the source.
+/// `consume` method **fully** consumes the source. If the processor knows
+/// the `max_output_size` of a chunk, the buffer is grown to fit it before
+/// the chunk is processed. Empty outputs are not written to the sink.
 pub struct Stream<B, W, P> {
     reader: B,
     writer: W,
@@ -46,21 +68,68 @@ impl<B: BufRead, W: Write, P: Process> Stream<B, W, P> {
     }
 
     /// Consume the source and fill the sink
-    pub fn consume(&mut self) -> IOResult<usize> {
+    pub fn consume(&mut self) -> Result<usize> {
+        self.consume_with_progress(|_, _| {})
+    }
+
+    /// Consume the source and fill the sink, returning the number of bytes
+    /// consumed and produced
+    ///
+    /// ```
+    /// use sqsh::core::{Stream, StreamStats};
+    /// use sqsh::processors::CRC32;
+    ///
+    /// let mut sink = Vec::<u8>::new();
+    /// let stats = Stream::from_slice("sqsh".as_bytes(), &mut sink, CRC32::new())
+    ///     .consume_with_stats()
+    ///     .unwrap();
+    /// assert_eq!(stats, StreamStats { consumed: 4, produced: 17 });
+    /// ```
+    pub fn consume_with_stats(&mut self) -> Result<StreamStats> {
+        let mut stats = StreamStats::default();
+        self.consume_with_progress(|consumed, produced| {
+            stats = StreamStats { consumed, produced }
+        })?;
+        Ok(stats)
+    }
+
+    /// Consume the source and fill the sink while reporting the progress
+    ///
+    /// After each write to the sink `progress` is called with the total
+    /// number of bytes consumed from the source and written to the sink so
+    /// far. When compressing the consumed bytes are the meaningful metric,
+    /// when decompressing the written bytes are.
+    pub fn consume_with_progress<F: FnMut(usize, usize)>(
+        &mut self,
+        mut progress: F,
+    ) -> Result<usize> {
         let mut consumed: usize = 0;
+        let mut produced: usize = 0;
         loop {
             let data = self.reader.fill_buf()?;
             let length = data.len();
             consumed += length;
             if length > 0 {
+                if let Some(bound) = self.processor.max_output_size(length) {
+                    self.buffer.reserve(bound);
+                }
                 self.processor.process(data, &mut self.buffer)?;
-                self.writer.write_all(&self.buffer)?;
+                if !self.buffer.is_empty() {
+                    self.writer.write_all(&self.buffer)?;
+                }
                 self.reader.consume(length);
-                self.buffer.clear()
+                produced += self.buffer.len();
+                self.buffer.clear();
+                progress(consumed, produced);
             } else {
                 self.processor.finish(&mut self.buffer)?;
-                self.writer.write_all(&self.buffer)?;
+                if !self.buffer.is_empty() {
+                    self.writer.write_all(&self.buffer)?;
+                }
                 self.writer.flush()?;
+                produced += self.buffer.len();
+                self.buffer.clear();
+                progress(consumed, produced);
                 break;
             }
         }
@@ -68,9 +137,190 @@ impl<B: BufRead, W: Write, P: Process> Stream<B, W, P> {
     }
 }
 
+impl<'a, W: Write, P: Process> Stream<&'a [u8], W, P> {
+    /// Create a new Stream object reading from an in-memory slice
+    ///
+    /// The slice is handed to the processor directly without an intermediate
+    /// read buffer.
+    ///
+    /// ```
+    /// use sqsh::core::Stream;
+    /// use sqsh::processors::Duplicate;
+    ///
+    /// let mut sink = Vec::<u8>::new();
+    /// let mut stream = Stream::from_slice("sqsh".as_bytes(), &mut sink, Duplicate::new());
+    /// assert_eq!(stream.consume().unwrap(), 4);
+    /// assert_eq!(sink, "sqsh".as_bytes());
+    /// ```
+    pub fn from_slice(data: &'a [u8], writer: W, processor: P) -> Self {
+        Stream::new(data, writer, processor)
+    }
+}
+
 impl<'a, B: BufRead, W: Write, P: Process> Iterator for &'a Stream<B, W, P> {
     type Item = &'a [u8];
     fn next(&mut self) -> Option<Self::Item> {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::{BwtDecoder, Duplicate, ShrinkOrBypass, ShrinkOrBypassDecoder};
+    use crate::Error;
+    use std::io::BufReader;
+
+    #[test]
+    fn decompression_progress() {
+        let source = "Awesome-string-baby".repeat(100).into_bytes();
+        let mut encoded = Vec::new();
+        Stream::from_slice(
+            &source,
+            &mut encoded,
+            ShrinkOrBypass::<Duplicate>::default(),
+        )
+        .consume()
+        .unwrap();
+
+        let mut decoded = Vec::new();
+        let mut reports = Vec::new();
+        let reader = BufReader::with_capacity(64, encoded.as_slice());
+        let decoder = ShrinkOrBypassDecoder::<Duplicate>::default();
+        Stream::new(reader, &mut decoded, decoder)
+            .consume_with_progress(|c, p| reports.push((c, p)))
+            .unwrap();
+
+        assert_eq!(decoded, source);
+        assert!(reports.len() > 2);
+        assert!(reports.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert_eq!(reports.last(), Some(&(encoded.len(), source.len())));
+    }
+
+    /// Writer recording the length of every write
+    #[derive(Default)]
+    struct Recorder {
+        writes: Vec<usize>,
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes.push(buf.len());
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Writer failing on every write
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "Closed",
+            ))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn error_kinds() {
+        let corrupt = [2, 0, 0, 0, 5, 0, 0, 0, 1, 2];
+        let err = Stream::from_slice(&corrupt, Vec::new(), BwtDecoder::new())
+            .consume()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+        let err = Stream::from_slice(&corrupt[..4], Vec::new(), BwtDecoder::new())
+            .consume()
+            .unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+        let err = Stream::from_slice("sqsh".as_bytes(), Broken, Duplicate::new())
+            .consume()
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(_)), "{err:?}");
+    }
+
+    #[test]
+    fn no_empty_writes() {
+        let mut recorder = Recorder::default();
+        Stream::from_slice("sqsh".as_bytes(), &mut recorder, Duplicate::new())
+            .consume()
+            .unwrap();
+        assert_eq!(recorder.writes, [4]);
+
+        let mut recorder = Recorder::default();
+        Stream::from_slice(&[], &mut recorder, Duplicate::new())
+            .consume()
+            .unwrap();
+        assert!(recorder.writes.is_empty());
+    }
+
+    /// Processor writing every byte three times and counting reallocations
+    #[derive(Default)]
+    struct Triple {
+        bound: bool,
+        reallocations: usize,
+    }
+
+    impl Process for Triple {
+        fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+            for byte in source.iter() {
+                let capacity = sink.capacity();
+                sink.extend([*byte; 3]);
+                self.reallocations += (sink.capacity() != capacity) as usize;
+            }
+            Ok(source.len())
+        }
+        fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+            Ok(0)
+        }
+        fn max_output_size(&self, input_len: usize) -> Option<usize> {
+            self.bound.then_some(3 * input_len)
+        }
+    }
+
+    #[test]
+    fn stats() {
+        let source = "Awesome-string-baby".repeat(100).into_bytes();
+        let mut sink = Vec::new();
+        let reader = BufReader::with_capacity(64, source.as_slice());
+        let stats = Stream::new(reader, &mut sink, Triple::default())
+            .consume_with_stats()
+            .unwrap();
+        assert_eq!(stats.consumed, source.len());
+        assert_eq!(stats.produced, sink.len());
+        assert_eq!(stats.ratio(), Some(1.0 / 3.0));
+
+        let stats = Stream::from_slice(&[], Vec::new(), Duplicate::new())
+            .consume_with_stats()
+            .unwrap();
+        assert_eq!(stats, StreamStats::default());
+        assert_eq!(stats.ratio(), None);
+    }
+
+    #[test]
+    fn preallocation() {
+        let source = vec![7; 20_000];
+        for bound in [false, true] {
+            let mut sink = Vec::new();
+            let reader = BufReader::with_capacity(8192, source.as_slice());
+            let mut stream = Stream::new(
+                reader,
+                &mut sink,
+                Triple {
+                    bound,
+                    ..Default::default()
+                },
+            );
+            stream.consume().unwrap();
+            let reallocations = stream.processor.reallocations;
+            assert_eq!(reallocations == 0, bound, "{reallocations} reallocations");
+            assert_eq!(sink.len(), 60_000);
+        }
+    }
+}
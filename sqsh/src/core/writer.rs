@@ -0,0 +1,167 @@
+//! # Writer
+//!
+//! This module provides an adapter applying a processor to everything
+//! written into an inner writer. It is the push based counterpart of the
+//! `Stream`, which pulls the data from a source.
+use crate::core::process::Process;
+use crate::Result;
+use log::warn;
+use std::io::{Result as IOResult, Write};
+
+/// ProcessWriter passes all written data through the processor before
+/// writing it to the inner writer.
+///
+/// Processors may hold back data until the end of the stream, therefore the
+/// writer **must** be finished with `finish`, which also returns the inner
+/// writer. Dropping an unfinished writer only logs a warning, as `drop`
+/// cannot report errors, and the written output is likely incomplete.
+///
+/// ```
+/// use sqsh::core::ProcessWriter;
+/// use sqsh::processors::Duplicate;
+/// use std::io::Write;
+///
+/// let mut writer = ProcessWriter::new(Vec::new(), Duplicate::new());
+/// writer.write_all("sqsh".as_bytes()).unwrap();
+/// assert_eq!(writer.finish().unwrap(), "sqsh".as_bytes());
+/// ```
+pub struct ProcessWriter<W: Write, P: Process> {
+    writer: Option<W>,
+    processor: P,
+    buffer: Vec<u8>,
+    pending: bool,
+}
+
+impl<W: Write, P: Process> ProcessWriter<W, P> {
+    /// Create a new ProcessWriter around the inner writer
+    pub fn new(writer: W, processor: P) -> Self {
+        ProcessWriter {
+            writer: Some(writer),
+            processor,
+            buffer: Vec::new(),
+            pending: false,
+        }
+    }
+
+    /// Finish the processor, flush all data and return the inner writer
+    pub fn finish(mut self) -> Result<W> {
+        let mut writer = self.writer.take().expect("Writer is only taken once");
+        self.buffer.clear();
+        self.processor.finish(&mut self.buffer)?;
+        writer.write_all(&self.buffer)?;
+        writer.flush()?;
+        self.pending = false;
+        Ok(writer)
+    }
+}
+
+impl<W: Write, P: Process> Write for ProcessWriter<W, P> {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("Writer is only taken in finish");
+        self.pending = true;
+        self.buffer.clear();
+        self.processor.process(buf, &mut self.buffer)?;
+        writer.write_all(&self.buffer)?;
+        Ok(buf.len())
+    }
+
+    /// Flush the pending data of the processor and the inner writer
+    ///
+    /// Processors not supporting `Process::flush` still hold back their
+    /// data until `finish`.
+    fn flush(&mut self) -> IOResult<()> {
+        match self.writer.as_mut() {
+            Some(writer) => {
+                self.buffer.clear();
+                self.processor.flush(&mut self.buffer)?;
+                writer.write_all(&self.buffer)?;
+                writer.flush()
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write, P: Process> Drop for ProcessWriter<W, P> {
+    fn drop(&mut self) {
+        if self.pending {
+            warn!("ProcessWriter dropped without finish, output may be incomplete");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::{Duplicate, ShrinkOrBypass, ShrinkOrBypassDecoder};
+
+    /// Encoder holding back data and writing it as `(length, data)` frames
+    #[derive(Default)]
+    struct FrameEncoder {
+        pending: Vec<u8>,
+    }
+
+    impl Process for FrameEncoder {
+        fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> Result<usize> {
+            self.pending.extend(source);
+            Ok(source.len())
+        }
+        fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+            self.flush(sink)
+        }
+        fn flush(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+            for frame in self.pending.chunks(u8::MAX as usize) {
+                sink.push(frame.len() as u8);
+                sink.extend(frame);
+            }
+            self.pending.clear();
+            Ok(0)
+        }
+    }
+
+    fn decode_frames(mut data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::new();
+        while let Some((&len, rest)) = data.split_first() {
+            result.extend(&rest[..len as usize]);
+            data = &rest[len as usize..];
+        }
+        result
+    }
+
+    #[test]
+    fn flush_mid_stream() {
+        let mut sink = Vec::new();
+        let mut writer = ProcessWriter::new(&mut sink, FrameEncoder::default());
+        writer.write_all("Awesome-".as_bytes()).unwrap();
+        writer.flush().unwrap();
+        writer.write_all("string-baby".as_bytes()).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(sink[0], 8);
+        assert_eq!(decode_frames(&sink[..9]), "Awesome-".as_bytes());
+        assert_eq!(decode_frames(&sink), "Awesome-string-baby".as_bytes());
+    }
+
+    #[test]
+    fn flush_on_finish() {
+        let source = "Awesome-string-baby".as_bytes();
+        let mut sink = Vec::new();
+        {
+            let mut writer = ProcessWriter::new(&mut sink, ShrinkOrBypass::<Duplicate>::default());
+            writer.write_all(source).unwrap();
+            writer.flush().unwrap();
+        }
+        assert!(sink.is_empty());
+
+        let mut writer = ProcessWriter::new(Vec::new(), ShrinkOrBypass::<Duplicate>::default());
+        writer.write_all(source).unwrap();
+        let encoded = writer.finish().unwrap();
+
+        let mut writer =
+            ProcessWriter::new(Vec::new(), ShrinkOrBypassDecoder::<Duplicate>::default());
+        writer.write_all(&encoded).unwrap();
+        assert_eq!(writer.finish().unwrap(), source);
+    }
+}
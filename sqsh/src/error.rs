@@ -0,0 +1,124 @@
+//! # Error
+//!
+//! Errors of the processors. The variants separate corrupt or incomplete
+//! data from failures of the underlying I/O, which is what a caller needs
+//! to decide whether a retry can help.
+//!
+//! Conversions to and from `std::io::Error` are lossless: a sqsh error
+//! passed through an `io::Write` implementation like `ProcessWriter` is
+//! restored to its original variant. The `Io` variant and the conversions
+//! require the `std` feature.
+use crate::core::ChecksumMismatch;
+use alloc::string::String;
+use core::fmt::Display;
+#[cfg(feature = "std")]
+use std::io::ErrorKind;
+
+/// Error of a processor or stream
+#[derive(Debug)]
+pub enum Error {
+    /// The data ended in the middle of a code, frame or header
+    Truncated(String),
+    /// The data contains a code or value which cannot be decoded
+    InvalidCode(String),
+    /// The checksum of the decoded data differs from the stored one
+    ChecksumMismatch(ChecksumMismatch),
+    /// Reading or writing the data failed
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+/// Result of the processors with the crate error type
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl Error {
+    /// Create a new `Truncated` error with the given message
+    pub fn truncated(msg: impl Into<String>) -> Self {
+        Error::Truncated(msg.into())
+    }
+
+    /// Create a new `InvalidCode` error with the given message
+    pub fn invalid_code(msg: impl Into<String>) -> Self {
+        Error::InvalidCode(msg.into())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Truncated(msg) | Error::InvalidCode(msg) => write!(f, "{msg}"),
+            Error::ChecksumMismatch(mismatch) => write!(f, "{mismatch}"),
+            #[cfg(feature = "std")]
+            Error::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        if e.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = e.into_inner().expect("Inner error is present");
+            return *inner
+                .downcast::<Error>()
+                .expect("Inner error is a sqsh error");
+        }
+        Error::Io(e)
+    }
+}
+
+/// Truncated data maps to `UnexpectedEof`, the remaining data errors to
+/// `InvalidData`
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            Error::Truncated(_) => std::io::Error::new(ErrorKind::UnexpectedEof, e),
+            _ => std::io::Error::new(ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn io_roundtrip() {
+        let e: std::io::Error = Error::truncated("Truncated frame").into();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+        assert!(matches!(Error::from(e), Error::Truncated(msg) if msg == "Truncated frame"));
+
+        let mismatch = ChecksumMismatch {
+            expected: 1,
+            calculated: 2,
+        };
+        let e: std::io::Error = Error::ChecksumMismatch(mismatch).into();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert!(matches!(Error::from(e), Error::ChecksumMismatch(m) if m == mismatch));
+
+        let e = Error::from(std::io::Error::new(ErrorKind::NotFound, "missing"));
+        assert!(matches!(&e, Error::Io(inner) if inner.kind() == ErrorKind::NotFound));
+        assert_eq!(std::io::Error::from(e).kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(
+            Error::invalid_code("Invalid code").to_string(),
+            "Invalid code"
+        );
+        let mismatch = ChecksumMismatch {
+            expected: 1,
+            calculated: 2,
+        };
+        assert_eq!(
+            Error::ChecksumMismatch(mismatch).to_string(),
+            "Checksum mismatch: expected 0x1, calculated 0x2"
+        );
+    }
+}
@@ -4,6 +4,35 @@
 //! Currently the library is under private development. It will be released Q4 2022.
 //!
 //! 🤿
+//!
+//! ## Reproducibility
+//!
+//! All processors are deterministic: the same input processed by a processor
+//! of the same configuration and library version yields byte-identical
+//! output, no matter how the input is split into `process` calls. No
+//! processor depends on randomness, hashing seeds or the iteration order of
+//! hash based collections for what it writes to the sink. Tests check this
+//! with fresh processors and inputs spanning several chunks of a `Stream`.
+//!
+//! The exception is `processors::best_of_within`, where the time budget
+//! decides which candidates are tried.
+//!
+//! ## `no_std`
+//!
+//! The default `std` feature can be disabled to use the library with only
+//! the `alloc` crate. The `Process` traits, `Pipeline`, the checksums and
+//! most processors remain available, the list in `processors` names the
+//! exceptions. Everything built on `std::io` requires `std`: the `Io` error
+//! variant, `Stream`, `ProcessWriter`, `LineFramedStream` and `MultiWriter`.
+//! So do `core::ProbTable` and the `stats` module.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod core;
+mod error;
 pub mod processors;
+#[cfg(feature = "std")]
+pub mod stats;
+
+pub use error::{Error, Result};
@@ -2,22 +2,43 @@
 //!
 //! Implementation of the Adler32 checksum algorithm as described
 //! [here](https://en.wikipedia.org/wiki/Adler-32).
-use crate::core::{Checksum, Process};
+//!
+//! Both sums are kept in `u32` and reduced modulo 65521 after at most 5552
+//! bytes, the largest count for which the sums can not overflow.
+use crate::core::{Checksum, ChecksumOutput, Process, Reset};
+use alloc::vec::Vec;
+use core::fmt::Display;
 use log::{info, trace};
-use std::fmt::Display;
+
+/// Largest prime below 2^16
+const MOD_ADLER: u32 = 65521;
+/// Largest number of bytes which can be summed before `b` may overflow
+const NMAX: usize = 5552;
 
 /// Adler32 struct to save normal and aggregated sum
 #[derive(Debug)]
 pub struct Adler32 {
-    a: u16,
-    b: u16,
+    a: u32,
+    b: u32,
+    output: ChecksumOutput,
+    finished: bool,
 }
 
 impl Adler32 {
-    /// Generate new Adler32 struct
+    /// Generate new Adler32 struct writing the `Display` string in `finish`
     pub fn new() -> Self {
+        Self::with_output(ChecksumOutput::Text)
+    }
+
+    /// Generate new Adler32 struct writing the checksum in the given format
+    pub fn with_output(output: ChecksumOutput) -> Self {
         info!("New Adler32 checksum");
-        Adler32 { a: 1, b: 0 }
+        Adler32 {
+            a: 1,
+            b: 0,
+            output,
+            finished: false,
+        }
     }
 }
 
@@ -25,7 +46,7 @@ impl Checksum for Adler32 {
     type Output = u32;
 
     fn checksum(&self) -> u32 {
-        let result = ((self.b as u32) << 16) | self.a as u32;
+        let result = (self.b << 16) | self.a;
         info!("Adler32 Checksum: {}", result);
         result
     }
@@ -40,7 +61,7 @@ impl Default for Adler32 {
 
 /// Printing should display the checksum
 impl Display for Adler32 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let csum = self.checksum();
         write!(f, "Adler32<{csum:#010X}>")
     }
@@ -48,25 +69,43 @@ impl Display for Adler32 {
 
 /// Implementation of the Process trait for Adler32
 impl Process for Adler32 {
-    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> std::io::Result<usize> {
-        for byte in source.iter() {
-            self.a += *byte as u16 % u16::MAX;
-            self.b += self.a % u16::MAX;
-            trace!("Adler32 Update: {byte}, New State: {self:?}")
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> crate::Result<usize> {
+        for chunk in source.chunks(NMAX) {
+            for byte in chunk.iter() {
+                self.a += *byte as u32;
+                self.b += self.a;
+            }
+            self.a %= MOD_ADLER;
+            self.b %= MOD_ADLER;
+            trace!("Adler32 Update: {} bytes, New State: {self:?}", chunk.len())
         }
+        self.finished = false;
         Ok(source.len())
     }
-    fn finish(&mut self, sink: &mut Vec<u8>) -> std::io::Result<usize> {
-        let result = self.to_string();
-        sink.extend(result.as_bytes());
+    fn finish(&mut self, sink: &mut Vec<u8>) -> crate::Result<usize> {
+        if !self.finished {
+            self.output.write(self, sink);
+            self.finished = true;
+        }
         Ok(0)
     }
 }
 
+impl Reset for Adler32 {
+    fn reset(&mut self) {
+        self.a = 1;
+        self.b = 0;
+        self.finished = false;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core::checksum::tests::*;
+    use crate::core::process::tests::{
+        process_chunked, test_deterministic_process, test_finish_twice,
+    };
 
     #[test]
     fn adler32() {
@@ -75,9 +114,52 @@ mod tests {
         assert_checksum::<u32, Adler32>("This is great".as_bytes(), 0x20AF04C8);
     }
 
+    #[test]
+    fn long_input() {
+        let pattern: Vec<u8> = (0..6000u32).map(|i| (i * 31 % 251) as u8).collect();
+        assert_checksum::<u32, Adler32>(&pattern, 0x33917352);
+        // The largest bytes overflow 16-bit sums after a few hundred bytes
+        assert_checksum::<u32, Adler32>(&[0xFF; 100_000], 0x149A302C);
+        for chunk_size in [1, 7, 5553] {
+            let mut adler = Adler32::new();
+            process_chunked(&mut adler, &[0xFF; 100_000], chunk_size);
+            assert_eq!(adler.checksum(), 0x149A302C);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        assert_checksum::<u32, Adler32>("".as_bytes(), 0x00000001);
+        let mut model = Adler32::new();
+        let mut sink = Vec::<u8>::new();
+        model.finish(&mut sink).expect("Error");
+        assert_eq!(model.checksum(), 1);
+        assert_eq!(sink, "Adler32<0x00000001>".as_bytes());
+    }
+
     #[test]
     fn formatting() {
-        check_debug_format::<Adler32>("Adler32 { a: 1, b: 0 }");
+        check_debug_format::<Adler32>("Adler32 { a: 1, b: 0, output: Text, finished: false }");
         check_display_format::<Adler32>("Adler32<0x00000001>");
     }
+
+    #[test]
+    fn raw_output() {
+        let source = "Wikipedia".as_bytes();
+        let be = process_chunked(&mut Adler32::with_output(ChecksumOutput::RawBE), source, 4);
+        assert_eq!(u32::from_be_bytes(be.try_into().unwrap()), 0x11E60398);
+        let le = process_chunked(&mut Adler32::with_output(ChecksumOutput::RawLE), source, 4);
+        assert_eq!(u32::from_le_bytes(le.try_into().unwrap()), 0x11E60398);
+    }
+
+    #[test]
+    fn deterministic() {
+        test_deterministic_process::<Adler32>("Awesome-string-baby".as_bytes());
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(Adler32::new(), "Wikipedia".as_bytes());
+        test_finish_twice(Adler32::new(), &[]);
+    }
 }
@@ -0,0 +1,357 @@
+//! # Arithmetic coding
+//!
+//! Adaptive arithmetic coding as described
+//! [here](https://en.wikipedia.org/wiki/Arithmetic_coding), implemented as a
+//! 32-bit range coder with carry propagation.
+//!
+//! The model is a `ProbTable` which starts with a count of one for every byte
+//! value and is updated after each coded symbol. Encoder and decoder perform
+//! the same updates, so the model never needs to be transmitted. Once the
+//! total count reaches 2^16 all counts are halved to keep the precision of
+//! the coder and let the model adapt to changing statistics.
+//!
+//! The encoder writes its output while processing and flushes the coder in
+//! `finish`, followed by the number of coded symbols (`u64`, little-endian).
+//! As the symbol count is only known at the end, the decoder buffers its
+//! input and decodes it in `finish`. An empty source produces an empty
+//! output.
+use crate::core::{Decoder, Encoder, Invertible, ProbTable, Process, Reset};
+use crate::{Error, Result};
+use log::{debug, info};
+
+/// Lower bound of the range before it is renormalized
+const TOP: u32 = 1 << 24;
+/// Total count of the model at which the counts are halved
+const MAX_TOTAL: usize = 1 << 16;
+/// Upper bound of the symbols coded per byte
+///
+/// Every symbol has a probability of at most `1 - 255 / MAX_TOTAL` and
+/// consumes more than 1/180 bit, so a larger symbol count is corrupted.
+const MAX_SYMBOLS_PER_BYTE: u64 = 8 * 180;
+/// Size of the symbol count trailer in bytes
+const COUNT_SIZE: usize = 8;
+
+/// Adaptive model shared by encoder and decoder
+struct Model {
+    table: ProbTable<u8>,
+}
+
+impl Model {
+    fn new() -> Self {
+        Model {
+            table: (0..=u8::MAX).collect(),
+        }
+    }
+
+    /// Count `symbol` and rescale the model if necessary
+    fn update(&mut self, symbol: u8) {
+        self.table.insert(symbol);
+        if self.table.total() >= MAX_TOTAL {
+            debug!("Rescale arithmetic coding model");
+            let mut table = ProbTable::new();
+            for value in self.table.iter() {
+                let count = self.table.count(value).expect("Symbol is in the table");
+                table.feed(std::iter::repeat_n(*value, count.div_ceil(2)));
+            }
+            self.table = table;
+        }
+    }
+
+    /// Find the symbol whose cumulative range contains `target`
+    fn lookup(&self, target: usize) -> (u8, usize, usize) {
+        let mut low = 0;
+        for value in self.table.iter() {
+            let high = low + self.table.count(value).expect("Symbol is in the table");
+            if target < high {
+                return (*value, low, high);
+            }
+            low = high;
+        }
+        unreachable!("Target is below the total count")
+    }
+}
+
+/// Adaptive arithmetic encoder
+pub struct ArithmeticEncoder {
+    model: Model,
+    low: u64,
+    range: u32,
+    cache: u8,
+    cache_size: u64,
+    symbols: u64,
+}
+
+impl ArithmeticEncoder {
+    /// Create a new arithmetic encoder
+    pub fn new() -> Self {
+        info!("New arithmetic encoder");
+        ArithmeticEncoder {
+            model: Model::new(),
+            low: 0,
+            range: u32::MAX,
+            cache: 0,
+            cache_size: 1,
+            symbols: 0,
+        }
+    }
+
+    /// Write the top byte of `low`, delaying bytes which a carry could change
+    fn shift_low(&mut self, sink: &mut Vec<u8>) {
+        if self.low < 0xFF00_0000 || self.low > u32::MAX as u64 {
+            let carry = (self.low >> 32) as u8;
+            let mut byte = self.cache;
+            while self.cache_size > 0 {
+                sink.push(byte.wrapping_add(carry));
+                byte = 0xFF;
+                self.cache_size -= 1;
+            }
+            self.cache = (self.low >> 24) as u8;
+        }
+        self.cache_size += 1;
+        self.low = (self.low & 0x00FF_FFFF) << 8;
+    }
+}
+
+impl Default for ArithmeticEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for ArithmeticEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for symbol in source.iter() {
+            let (low, high) = self
+                .model
+                .table
+                .cumulative_frequency(symbol)
+                .expect("Model contains all symbols");
+            let r = self.range / self.model.table.total() as u32;
+            self.low += low as u64 * r as u64;
+            self.range = (high - low) as u32 * r;
+            while self.range < TOP {
+                self.range <<= 8;
+                self.shift_low(sink);
+            }
+            self.model.update(*symbol);
+        }
+        self.symbols += source.len() as u64;
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if self.symbols > 0 {
+            (0..5).for_each(|_| self.shift_low(sink));
+            sink.extend(self.symbols.to_le_bytes());
+        }
+        self.reset();
+        Ok(0)
+    }
+}
+
+impl Reset for ArithmeticEncoder {
+    fn reset(&mut self) {
+        self.model = Model::new();
+        self.low = 0;
+        self.range = u32::MAX;
+        self.cache = 0;
+        self.cache_size = 1;
+        self.symbols = 0;
+    }
+}
+
+impl Encoder for ArithmeticEncoder {}
+
+impl Invertible for ArithmeticEncoder {
+    type Decoder = ArithmeticDecoder;
+
+    fn inverse(&self) -> ArithmeticDecoder {
+        ArithmeticDecoder::new()
+    }
+}
+
+/// Adaptive arithmetic decoder restoring the output of the `ArithmeticEncoder`
+pub struct ArithmeticDecoder {
+    data: Vec<u8>,
+}
+
+impl ArithmeticDecoder {
+    /// Create a new arithmetic decoder
+    pub fn new() -> Self {
+        info!("New arithmetic decoder");
+        ArithmeticDecoder { data: Vec::new() }
+    }
+}
+
+impl Default for ArithmeticDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for ArithmeticDecoder {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> Result<usize> {
+        self.data.extend(source);
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        let data = std::mem::take(&mut self.data);
+        if data.is_empty() {
+            return Ok(0);
+        }
+        let truncated = || Error::truncated("Truncated arithmetic code");
+        let split = data.len().checked_sub(COUNT_SIZE).ok_or_else(truncated)?;
+        let (code, trailer) = data.split_at(split);
+        let symbols = u64::from_le_bytes(trailer.try_into().expect("8 bytes"));
+        debug!("Decode {symbols} arithmetic coded symbols");
+        if symbols > (code.len() as u64 + 1) * MAX_SYMBOLS_PER_BYTE {
+            let msg = format!("Invalid arithmetic code symbol count: {symbols}");
+            return Err(Error::invalid_code(msg));
+        }
+
+        // The first byte is the initial cache of the encoder and always zero
+        let mut bytes = code.iter().skip(1);
+        let mut value = 0u32;
+        for _ in 0..4 {
+            value = (value << 8) | *bytes.next().ok_or_else(truncated)? as u32;
+        }
+        let mut range = u32::MAX;
+        let mut model = Model::new();
+        for _ in 0..symbols {
+            let total = model.table.total() as u32;
+            let r = range / total;
+            let target = (value / r).min(total - 1);
+            let (symbol, low, high) = model.lookup(target as usize);
+            value -= low as u32 * r;
+            range = (high - low) as u32 * r;
+            while range < TOP {
+                range <<= 8;
+                value = (value << 8) | *bytes.next().ok_or_else(truncated)? as u32;
+            }
+            sink.push(symbol);
+            model.update(symbol);
+        }
+        // The flush of the encoder is consumed completely by the decoder
+        if bytes.next().is_some() {
+            return Err(Error::invalid_code("Trailing data after arithmetic code"));
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for ArithmeticDecoder {
+    fn reset(&mut self) {
+        self.data.clear();
+    }
+}
+
+impl Decoder for ArithmeticDecoder {
+    type Encoder = ArithmeticEncoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    /// Pseudo-random bytes of a xorshift generator
+    fn random(len: usize) -> Vec<u8> {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_byte() {
+        let encoded = test_roundtrip(ArithmeticEncoder::new(), &[42; 20_000]);
+        assert!(encoded.len() < 20_000 / 50);
+        test_roundtrip(ArithmeticEncoder::new(), &[0]);
+        test_roundtrip(ArithmeticEncoder::new(), &[255; 300]);
+    }
+
+    #[test]
+    fn uniform_random() {
+        let source = random(10_000);
+        let encoded = test_roundtrip(ArithmeticEncoder::new(), &source);
+        assert!(encoded.len() < source.len() + source.len() / 100);
+    }
+
+    #[test]
+    fn skewed() {
+        let source: Vec<u8> = random(10_000)
+            .iter()
+            .map(|b| b.leading_zeros() as u8)
+            .collect();
+        let encoded = test_roundtrip(ArithmeticEncoder::new(), &source);
+        assert!(encoded.len() < source.len() / 3);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(test_roundtrip(ArithmeticEncoder::new(), &[]).is_empty());
+    }
+
+    /// Encode `source` in a single call
+    fn encode(source: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        let mut encoder = ArithmeticEncoder::new();
+        encoder.process(source, &mut encoded).unwrap();
+        encoder.finish(&mut encoded).unwrap();
+        encoded
+    }
+
+    fn decode(encoded: &[u8]) -> Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        let mut decoder = ArithmeticDecoder::new();
+        decoder.process(encoded, &mut decoded)?;
+        decoder.finish(&mut decoded)?;
+        Ok(decoded)
+    }
+
+    #[test]
+    fn truncated() {
+        let mut encoded = encode(&random(1000));
+        let split = encoded.len() - COUNT_SIZE;
+        encoded.drain(split - 3..split);
+        let err = decode(&encoded).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+        let err = decode(&encoded[..4]).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+    }
+
+    #[test]
+    fn corrupted() {
+        let source = random(1000);
+        let encoded = encode(&source);
+        let mut inserted = encoded.clone();
+        inserted.insert(500, 0xAB);
+        assert!(decode(&inserted).is_err());
+        let mut removed = encoded.clone();
+        removed.drain(500..510);
+        assert!(decode(&removed).is_err());
+
+        let mut count = encoded.clone();
+        let split = count.len() - COUNT_SIZE;
+        count[split..].copy_from_slice(&u64::MAX.to_le_bytes());
+        let err = decode(&count).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+        assert_eq!(decode(&encoded).unwrap(), source);
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<ArithmeticEncoder>(&source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(ArithmeticEncoder::new(), "Wikipedia".as_bytes());
+        test_finish_twice(ArithmeticDecoder::new(), &encode("Wikipedia".as_bytes()));
+    }
+}
@@ -0,0 +1,129 @@
+//! # Batch
+//!
+//! Processing many small, independent buffers with the same processor. The
+//! processor and the output buffer are reused and reset between buffers
+//! instead of being constructed for each of them.
+use crate::core::{Process, Reset};
+use crate::Result;
+use alloc::vec::Vec;
+use log::trace;
+
+/// Aggregated statistics over all buffers of a batch
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchStats {
+    /// Number of processed buffers
+    pub buffers: usize,
+    /// Total number of bytes read from the buffers
+    pub consumed: usize,
+    /// Total number of bytes produced by the processor
+    pub produced: usize,
+}
+
+/// Compress independent buffers one after another reusing one processor
+pub struct BatchCompressor<P> {
+    processor: P,
+    buffer: Vec<u8>,
+    stats: BatchStats,
+}
+
+impl<P: Process + Reset> BatchCompressor<P> {
+    /// Create a new batch around the given processor
+    pub fn new(processor: P) -> Self {
+        BatchCompressor {
+            processor,
+            buffer: Vec::new(),
+            stats: Default::default(),
+        }
+    }
+
+    /// Process a single buffer independently of all previous buffers
+    ///
+    /// The returned slice is only valid until the next call.
+    pub fn compress_one(&mut self, data: &[u8]) -> Result<&[u8]> {
+        self.processor.reset();
+        self.buffer.clear();
+        self.processor.process(data, &mut self.buffer)?;
+        self.processor.finish(&mut self.buffer)?;
+        self.stats.buffers += 1;
+        self.stats.consumed += data.len();
+        self.stats.produced += self.buffer.len();
+        trace!("Batch buffer {}: {data:?}", self.stats.buffers);
+        Ok(&self.buffer)
+    }
+
+    /// Aggregated statistics over all processed buffers
+    pub fn stats(&self) -> BatchStats {
+        self.stats
+    }
+
+    /// Return the inner processor
+    pub fn into_inner(self) -> P {
+        self.processor
+    }
+}
+
+impl<P: Process + Reset + Default> Default for BatchCompressor<P> {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::{Duplicate, ShrinkOrBypass, ShrinkOrBypassDecoder};
+
+    /// Duplicate counting how often it was reset
+    #[derive(Default)]
+    struct CountingDuplicate {
+        inner: Duplicate,
+        resets: usize,
+    }
+
+    impl Process for CountingDuplicate {
+        fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+            self.inner.process(source, sink)
+        }
+        fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+            self.inner.finish(sink)
+        }
+    }
+
+    impl Reset for CountingDuplicate {
+        fn reset(&mut self) {
+            self.resets += 1;
+        }
+    }
+
+    fn buffers() -> Vec<Vec<u8>> {
+        (0..1000u32)
+            .map(|i| i.to_string().repeat(i as usize % 7).into_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut encoder = BatchCompressor::<ShrinkOrBypass<Duplicate>>::default();
+        let mut decoder = BatchCompressor::<ShrinkOrBypassDecoder<Duplicate>>::default();
+        let buffers = buffers();
+        for data in buffers.iter() {
+            let encoded = encoder.compress_one(data).unwrap().to_vec();
+            assert_eq!(decoder.compress_one(&encoded).unwrap(), data.as_slice());
+        }
+        let total: usize = buffers.iter().map(Vec::len).sum();
+        let stats = encoder.stats();
+        assert_eq!(stats.buffers, 1000);
+        assert_eq!(stats.consumed, total);
+        assert_eq!(stats.produced, total + 1000);
+        assert_eq!(decoder.stats().produced, total);
+    }
+
+    #[test]
+    fn reuse_processor() {
+        let mut batch = BatchCompressor::<CountingDuplicate>::default();
+        for data in buffers().iter() {
+            assert_eq!(batch.compress_one(data).unwrap(), data.as_slice());
+        }
+        assert_eq!(batch.into_inner().resets, 1000);
+    }
+}
@@ -0,0 +1,217 @@
+//! # Bit Packing
+//!
+//! Packing of bytes holding small values into fields of a fixed bit width.
+//! Every byte of the source must be below `2^width`; it is written as a
+//! `width` bit field, MSB-first, so the fields may span byte boundaries.
+//!
+//! The last byte is padded with zeros and followed by a trailer byte holding
+//! the number of padding bits, so the decoder knows how many fields to emit.
+//! An empty source produces an empty output.
+use crate::core::bits::{BitReader, BitWriter};
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use alloc::format;
+use alloc::vec::Vec;
+use log::info;
+
+fn check_width(width: u32) {
+    assert!(
+        (1..=8).contains(&width),
+        "Bit width must be between 1 and 8"
+    );
+}
+
+/// Encoder packing bytes into `width` bit fields
+pub struct BitPack {
+    width: u32,
+    writer: BitWriter,
+    started: bool,
+}
+
+impl BitPack {
+    /// Create a new packer of `width` bit fields
+    ///
+    /// Panics if `width` is not between 1 and 8.
+    pub fn new(width: u32) -> Self {
+        check_width(width);
+        info!("New bit packer with width {width}");
+        BitPack {
+            width,
+            writer: BitWriter::new(),
+            started: false,
+        }
+    }
+}
+
+impl Process for BitPack {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            if *byte as u32 >> self.width != 0 {
+                let msg = format!("Value {byte} does not fit into {} bits", self.width);
+                return Err(Error::invalid_code(msg));
+            }
+            self.writer.write(*byte as u64, self.width, sink);
+        }
+        self.started |= !source.is_empty();
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if self.started {
+            let padding = (8 - self.writer.pending()) % 8;
+            self.writer.finish(sink);
+            sink.push(padding as u8);
+        }
+        self.reset();
+        Ok(0)
+    }
+}
+
+impl Reset for BitPack {
+    fn reset(&mut self) {
+        self.writer.reset();
+        self.started = false;
+    }
+}
+
+impl Encoder for BitPack {}
+
+impl Invertible for BitPack {
+    type Decoder = BitUnpack;
+
+    fn inverse(&self) -> BitUnpack {
+        BitUnpack::new(self.width)
+    }
+}
+
+/// Decoder restoring the bytes of the `BitPack`
+pub struct BitUnpack {
+    width: u32,
+    reader: BitReader,
+    /// The last two bytes, which may hold padding and the trailer
+    held: Vec<u8>,
+}
+
+impl BitUnpack {
+    /// Create a new unpacker of `width` bit fields
+    ///
+    /// Panics if `width` is not between 1 and 8.
+    pub fn new(width: u32) -> Self {
+        check_width(width);
+        info!("New bit unpacker with width {width}");
+        BitUnpack {
+            width,
+            reader: BitReader::new(),
+            held: Vec::with_capacity(2),
+        }
+    }
+}
+
+impl Process for BitUnpack {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            self.held.push(*byte);
+            if self.held.len() > 2 {
+                self.reader.push(self.held.remove(0));
+                while let Some(value) = self.reader.read(self.width) {
+                    sink.push(value as u8);
+                }
+            }
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        let result = match self.held[..] {
+            [] => Ok(0),
+            [last, padding] if padding < 8 => {
+                self.reader.push(last);
+                while self.reader.pending() >= self.width + padding as u32 {
+                    let value = self.reader.read(self.width).expect("Enough bits pending");
+                    sink.push(value as u8);
+                }
+                if self.reader.pending() == padding as u32 && self.reader.remainder() == 0 {
+                    Ok(0)
+                } else {
+                    Err(Error::invalid_code("Invalid bit packing padding"))
+                }
+            }
+            [_, padding] => {
+                let msg = format!("Invalid bit packing trailer: {padding}");
+                Err(Error::invalid_code(msg))
+            }
+            _ => Err(Error::truncated("Missing bit packing trailer")),
+        };
+        self.reset();
+        result
+    }
+}
+
+impl Reset for BitUnpack {
+    fn reset(&mut self) {
+        self.reader.reset();
+        self.held.clear();
+    }
+}
+
+impl Decoder for BitUnpack {
+    type Encoder = BitPack;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    #[test]
+    fn widths() {
+        let source: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+        for width in 1..=8 {
+            let mask = (1u32 << width) - 1;
+            let values: Vec<u8> = source.iter().map(|b| (*b as u32 & mask) as u8).collect();
+            let encoded = test_roundtrip(BitPack::new(width), &values);
+            assert_eq!(encoded.len(), (1000 * width as usize).div_ceil(8) + 1);
+        }
+    }
+
+    #[test]
+    fn padding() {
+        let encoded = test_roundtrip(BitPack::new(3), &[0b101, 0b011, 0b111]);
+        assert_eq!(encoded, [0b1010_1111, 0b1000_0000, 7]);
+        // A padding of more than one field must not produce extra values
+        test_roundtrip(BitPack::new(2), &[3]);
+        test_roundtrip(BitPack::new(1), &[1; 9]);
+        assert!(test_roundtrip(BitPack::new(5), &[]).is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        let err = BitPack::new(4).process(&[16], &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+
+        let mut decoder = BitUnpack::new(3);
+        decoder.process(&[0b1010_1111], &mut Vec::new()).unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+        decoder
+            .process(&[0b1010_1111, 0b1000_0000, 8], &mut Vec::new())
+            .unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+        decoder
+            .process(&[0b1010_1111, 0b1000_0000, 3], &mut Vec::new())
+            .unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+    }
+
+    #[test]
+    fn deterministic() {
+        let source: Vec<u8> = multi_chunk_source().iter().map(|b| b & 7).collect();
+        test_deterministic_with(|| BitPack::new(3), &source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(BitPack::new(4), &[1, 2, 3]);
+        test_finish_twice(BitUnpack::new(4), &[0x12, 0x30, 4]);
+    }
+}
@@ -0,0 +1,125 @@
+//! # Budget
+//!
+//! Selection of the best of several processors within a time budget. The
+//! candidates are tried in the given order, so callers should put the
+//! cheapest processors first. Once the budget is spent no further candidate
+//! is started; a running candidate is not interrupted.
+use crate::core::Process;
+use log::{debug, info};
+use std::time::{Duration, Instant};
+
+/// Result of `best_of_within`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestOf {
+    /// Smallest output found, the data itself if no candidate was smaller
+    pub output: Vec<u8>,
+    /// Index of the candidate producing `output`, `None` for the stored data
+    pub best: Option<usize>,
+    /// Indices of all candidates which were run, including failed ones
+    pub evaluated: Vec<usize>,
+    /// Whether the budget ran out before all candidates were run
+    pub stopped_early: bool,
+}
+
+/// Process `data` with the `candidates` until `budget` is spent and return
+/// the smallest output
+///
+/// The stored data is always a valid result, so this never fails. A
+/// candidate returning an error is skipped. Every run candidate is left
+/// finished, failed candidates need a reset before they are reused.
+///
+/// ```
+/// use sqsh::processors::{best_of_within, Duplicate, RiceEncoder};
+/// use sqsh::core::Process;
+/// use std::time::Duration;
+///
+/// let data = [0, 1, 0, 2, 1, 0, 0, 1];
+/// let (mut duplicate, mut rice) = (Duplicate::new(), RiceEncoder::with_k(0));
+/// let mut candidates: [&mut dyn Process; 2] = [&mut duplicate, &mut rice];
+/// let result = best_of_within(&mut candidates, &data, Duration::from_secs(1));
+/// assert_eq!(result.best, Some(1));
+/// assert_eq!(result.evaluated, [0, 1]);
+/// ```
+pub fn best_of_within(
+    candidates: &mut [&mut dyn Process],
+    data: &[u8],
+    budget: Duration,
+) -> BestOf {
+    let start = Instant::now();
+    let mut result = BestOf {
+        output: data.to_vec(),
+        best: None,
+        evaluated: Vec::new(),
+        stopped_early: false,
+    };
+    let mut sink = Vec::new();
+    for (i, candidate) in candidates.iter_mut().enumerate() {
+        if start.elapsed() >= budget {
+            info!("Budget spent after {} candidates", result.evaluated.len());
+            result.stopped_early = true;
+            break;
+        }
+        result.evaluated.push(i);
+        sink.clear();
+        let outcome = candidate
+            .process(data, &mut sink)
+            .and_then(|_| candidate.finish(&mut sink));
+        match outcome {
+            Ok(_) if sink.len() < result.output.len() => {
+                debug!("Candidate {i} produced {} bytes", sink.len());
+                std::mem::swap(&mut result.output, &mut sink);
+                result.best = Some(i);
+            }
+            Ok(_) => debug!("Candidate {i} produced {} bytes", sink.len()),
+            Err(e) => debug!("Candidate {i} failed: {e}"),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::process_chunked;
+    use crate::processors::{BitPack, Duplicate, EliasGammaEncoder};
+
+    #[test]
+    fn smallest_output() {
+        let data = vec![1; 1000];
+        let mut duplicate = Duplicate::new();
+        let mut gamma = EliasGammaEncoder::new();
+        let mut pack = BitPack::new(1);
+        let mut candidates: [&mut dyn Process; 3] = [&mut duplicate, &mut gamma, &mut pack];
+        let result = best_of_within(&mut candidates, &data, Duration::from_secs(10));
+        assert_eq!(result.best, Some(2));
+        assert_eq!(
+            result.output,
+            process_chunked(&mut BitPack::new(1), &data, 4096)
+        );
+        assert_eq!(result.evaluated, [0, 1, 2]);
+        assert!(!result.stopped_early);
+    }
+
+    #[test]
+    fn failing_candidate() {
+        let data = vec![200; 100];
+        let mut pack = BitPack::new(4);
+        let mut candidates: [&mut dyn Process; 1] = [&mut pack];
+        let result = best_of_within(&mut candidates, &data, Duration::from_secs(10));
+        assert_eq!(result.best, None);
+        assert_eq!(result.output, data);
+        assert_eq!(result.evaluated, [0]);
+    }
+
+    #[test]
+    fn budget_spent() {
+        let data = vec![1; 1000];
+        let mut pack = BitPack::new(1);
+        let mut candidates: [&mut dyn Process; 1] = [&mut pack];
+        let result = best_of_within(&mut candidates, &data, Duration::ZERO);
+        assert_eq!(result.output, data);
+        assert_eq!(result.best, None);
+        assert!(result.evaluated.is_empty());
+        assert!(result.stopped_early);
+    }
+}
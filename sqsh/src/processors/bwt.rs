@@ -0,0 +1,313 @@
+//! # Burrows-Wheeler Transform
+//!
+//! Implementation of the Burrows-Wheeler transform as described
+//! [here](https://en.wikipedia.org/wiki/Burrows%E2%80%93Wheeler_transform).
+//!
+//! The transform sorts all rotations of a block and emits the last column
+//! of the sorted rotations. Bytes with similar contexts end up next to each
+//! other, which makes the output well suited for move-to-front and run-length
+//! encoding.
+//!
+//! The encoder works in block mode: the source is split into blocks of
+//! `block_size` bytes, the last block may be shorter. Every block is written
+//! as a frame consisting of the block length (`u32`, little-endian), the
+//! primary index, i.e. the row of the original block in the sorted
+//! rotations (`u32`, little-endian), and the transformed block.
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use log::{debug, info};
+
+/// Default block size in bytes
+const DEFAULT_BLOCK_SIZE: usize = 100_000;
+/// Size of the frame header in bytes
+const HEADER_SIZE: usize = 8;
+
+/// Sort all rotations of `block` and return their start positions
+///
+/// Uses prefix doubling: in round `k` the rotations are ordered by their
+/// first `2^k` bytes, using the ranks of the previous round.
+fn sorted_rotations(block: &[u8]) -> Vec<usize> {
+    let n = block.len();
+    let mut rotations: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = block.iter().map(|b| *b as usize).collect();
+    let mut next = vec![0; n];
+    let mut length = 1;
+    loop {
+        let key = |i: usize| (rank[i], rank[(i + length) % n]);
+        rotations.sort_unstable_by_key(|&i| key(i));
+        next[rotations[0]] = 0;
+        for w in 1..n {
+            let increment = (key(rotations[w - 1]) != key(rotations[w])) as usize;
+            next[rotations[w]] = next[rotations[w - 1]] + increment;
+        }
+        core::mem::swap(&mut rank, &mut next);
+        if rank[rotations[n - 1]] == n - 1 || length >= n {
+            break;
+        }
+        length *= 2;
+    }
+    rotations
+}
+
+/// Transform a single block and write its frame to the sink
+fn encode_block(block: &[u8], sink: &mut Vec<u8>) {
+    let n = block.len();
+    let rotations = sorted_rotations(block);
+    let primary = rotations
+        .iter()
+        .position(|r| *r == 0)
+        .expect("Rotation of the block itself is present");
+    sink.extend((n as u32).to_le_bytes());
+    sink.extend((primary as u32).to_le_bytes());
+    sink.extend(rotations.iter().map(|r| block[(r + n - 1) % n]));
+}
+
+/// Invert the transform of a single block using the LF-mapping
+fn decode_block(last: &[u8], primary: usize, sink: &mut Vec<u8>) {
+    let mut counts = [0usize; 256];
+    last.iter().for_each(|b| counts[*b as usize] += 1);
+    let mut first = [0usize; 256];
+    let mut sum = 0;
+    for (c, count) in counts.iter().enumerate() {
+        first[c] = sum;
+        sum += count;
+    }
+    let mut seen = [0usize; 256];
+    let lf: Vec<usize> = last
+        .iter()
+        .map(|b| {
+            let c = *b as usize;
+            seen[c] += 1;
+            first[c] + seen[c] - 1
+        })
+        .collect();
+    let start = sink.len();
+    sink.resize(start + last.len(), 0);
+    let mut row = primary;
+    for k in (0..last.len()).rev() {
+        sink[start + k] = last[row];
+        row = lf[row];
+    }
+}
+
+/// Burrows-Wheeler encoder transforming the source block by block
+pub struct BwtEncoder {
+    block_size: usize,
+    block: Vec<u8>,
+}
+
+impl BwtEncoder {
+    /// Create a new encoder with the default block size of 100 000 bytes
+    pub fn new() -> Self {
+        Self::with_block_size(DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Create a new encoder for a compression level from 1 to 9
+    ///
+    /// Like bzip2, level `l` uses blocks of `l * 100 000` bytes. Larger
+    /// blocks group more similar contexts at the cost of memory and time.
+    ///
+    /// Panics if `level` is not between 1 and 9.
+    pub fn with_level(level: u32) -> Self {
+        assert!((1..=9).contains(&level), "Level must be between 1 and 9");
+        Self::with_block_size(level as usize * DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Create a new encoder with blocks of `block_size` bytes
+    ///
+    /// Panics if the block size is zero or does not fit into a `u32`.
+    pub fn with_block_size(block_size: usize) -> Self {
+        assert!(
+            block_size > 0 && block_size <= u32::MAX as usize,
+            "Block size must be positive and fit into 32 bits"
+        );
+        info!("New BWT encoder with block size {block_size}");
+        BwtEncoder {
+            block_size,
+            block: Vec::new(),
+        }
+    }
+}
+
+impl Default for BwtEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for BwtEncoder {
+    fn process(&mut self, mut source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        let length = source.len();
+        while !source.is_empty() {
+            let missing = (self.block_size - self.block.len()).min(source.len());
+            self.block.extend(&source[..missing]);
+            source = &source[missing..];
+            if self.block.len() == self.block_size {
+                debug!("Transform full block of {} bytes", self.block.len());
+                encode_block(&self.block, sink);
+                self.block.clear();
+            }
+        }
+        Ok(length)
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if !self.block.is_empty() {
+            debug!("Transform final block of {} bytes", self.block.len());
+            encode_block(&self.block, sink);
+            self.block.clear();
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for BwtEncoder {
+    fn reset(&mut self) {
+        self.block.clear();
+    }
+}
+
+impl Encoder for BwtEncoder {}
+
+impl Invertible for BwtEncoder {
+    type Decoder = BwtDecoder;
+
+    fn inverse(&self) -> BwtDecoder {
+        BwtDecoder::new()
+    }
+}
+
+/// Burrows-Wheeler decoder restoring the blocks of the `BwtEncoder`
+///
+/// The block size is read from the frames, so no configuration is needed.
+pub struct BwtDecoder {
+    buffer: Vec<u8>,
+}
+
+impl BwtDecoder {
+    /// Create a new decoder
+    pub fn new() -> Self {
+        info!("New BWT decoder");
+        BwtDecoder { buffer: Vec::new() }
+    }
+}
+
+impl Default for BwtDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for BwtDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.buffer.extend(source);
+        let mut offset = 0;
+        while self.buffer.len() - offset >= HEADER_SIZE {
+            let header = &self.buffer[offset..offset + HEADER_SIZE];
+            let n = u32::from_le_bytes(header[..4].try_into().expect("4 bytes")) as usize;
+            let primary = u32::from_le_bytes(header[4..].try_into().expect("4 bytes")) as usize;
+            if primary >= n {
+                let msg = format!("Invalid BWT frame: primary index {primary}, length {n}");
+                return Err(Error::invalid_code(msg));
+            }
+            let start = offset + HEADER_SIZE;
+            if self.buffer.len() - start < n {
+                break;
+            }
+            decode_block(&self.buffer[start..start + n], primary, sink);
+            offset = start + n;
+        }
+        self.buffer.drain(..offset);
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        if self.buffer.is_empty() {
+            return Ok(0);
+        }
+        self.buffer.clear();
+        Err(Error::truncated("Truncated BWT frame"))
+    }
+}
+
+impl Reset for BwtDecoder {
+    fn reset(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+impl Decoder for BwtDecoder {
+    type Encoder = BwtEncoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    #[test]
+    fn banana() {
+        let encoded = test_roundtrip(BwtEncoder::new(), "banana".as_bytes());
+        assert_eq!(encoded[..4], 6u32.to_le_bytes());
+        assert_eq!(encoded[4..8], 3u32.to_le_bytes());
+        assert_eq!(encoded[8..], *"nnbaaa".as_bytes());
+    }
+
+    #[test]
+    fn blocks() {
+        let source = "Awesome-string-baby".repeat(3).into_bytes();
+        let encoded = test_roundtrip(BwtEncoder::with_block_size(8), &source);
+        // 7 full blocks and a final partial block of 1 byte
+        assert_eq!(encoded.len(), source.len() + 8 * HEADER_SIZE);
+        assert_eq!(
+            encoded[encoded.len() - 9..encoded.len() - 5],
+            1u32.to_le_bytes()
+        );
+        test_roundtrip(BwtEncoder::with_block_size(source.len()), &source);
+    }
+
+    #[test]
+    fn edge_cases() {
+        assert!(test_roundtrip(BwtEncoder::new(), &[]).is_empty());
+        test_roundtrip(BwtEncoder::new(), &[42]);
+        test_roundtrip(BwtEncoder::new(), &[7; 1000]);
+        test_roundtrip(BwtEncoder::new(), "abababababab".as_bytes());
+        let all: Vec<u8> = (0..=255).cycle().take(5000).collect();
+        test_roundtrip(BwtEncoder::with_block_size(1234), &all);
+    }
+
+    #[test]
+    fn levels() {
+        for level in 1..=9 {
+            let encoder = BwtEncoder::with_level(level);
+            assert_eq!(encoder.block_size, level as usize * 100_000);
+            test_roundtrip(encoder, "banana bandana cabana".as_bytes());
+        }
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut decoder = BwtDecoder::new();
+        let frame = [2, 0, 0, 0, 5, 0, 0, 0, 1, 2];
+        let err = decoder.process(&frame, &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+
+        let mut decoder = BwtDecoder::new();
+        decoder.process(&frame[..6], &mut Vec::new()).unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<BwtEncoder>(&source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(BwtEncoder::with_block_size(4), "Wikipedia".as_bytes());
+        test_finish_twice(BwtDecoder::new(), &[3, 0, 0, 0, 1, 0, 0, 0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,146 @@
+//! # Checked Checksum
+//!
+//! Verification of a checksum within the processing. The wrapper calculates
+//! the checksum like the inner checksum and compares it against an expected
+//! value at the end of the stream, so corruption is detected as early as
+//! possible.
+use crate::core::{Checksum, ChecksumMismatch, Process, Reset};
+use crate::{Error, Result};
+use alloc::vec::Vec;
+use core::fmt::Display;
+use log::{info, warn};
+
+/// Checksum returning an error in `finish` if it differs from the expected
+/// value
+pub struct CheckedChecksum<C: Checksum> {
+    inner: C,
+    expected: Option<C::Output>,
+}
+
+impl<C: Checksum> CheckedChecksum<C> {
+    /// Wrap the inner checksum without an expected value
+    pub fn new(inner: C) -> Self {
+        CheckedChecksum {
+            inner,
+            expected: None,
+        }
+    }
+
+    /// Set the value the checksum is expected to have at the end
+    pub fn expect(mut self, value: C::Output) -> Self {
+        self.expected = Some(value);
+        self
+    }
+}
+
+impl<C: Checksum + Default> Default for CheckedChecksum<C> {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<C> Process for CheckedChecksum<C>
+where
+    C: Checksum,
+    C::Output: Copy + PartialEq + Into<u64>,
+{
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.inner.process(source, sink)
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        let result = self.inner.finish(sink)?;
+        let calculated = self.inner.checksum();
+        match self.expected {
+            Some(expected) if expected != calculated => {
+                let mismatch = ChecksumMismatch {
+                    expected: expected.into(),
+                    calculated: calculated.into(),
+                };
+                warn!("{mismatch}");
+                Err(Error::ChecksumMismatch(mismatch))
+            }
+            Some(_) => {
+                info!("Checksum verified");
+                Ok(result)
+            }
+            None => Ok(result),
+        }
+    }
+    fn flush(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        self.inner.flush(sink)
+    }
+}
+
+impl<C> Checksum for CheckedChecksum<C>
+where
+    C: Checksum,
+    C::Output: Copy + PartialEq + Into<u64>,
+{
+    type Output = C::Output;
+
+    fn checksum(&self) -> Self::Output {
+        self.inner.checksum()
+    }
+}
+
+impl<C: Checksum + Reset> Reset for CheckedChecksum<C> {
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl<C: Checksum + Display> Display for CheckedChecksum<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::{process_chunked, test_finish_twice};
+    use crate::processors::{Adler32, CRC32};
+
+    #[test]
+    fn matching() {
+        let mut crc = CheckedChecksum::<CRC32>::default().expect(0xadaac02e);
+        assert_eq!(
+            process_chunked(&mut crc, b"Wikipedia", 4),
+            b"CRC32<0xADAAC02E>"
+        );
+        let mut adler = CheckedChecksum::new(Adler32::new()).expect(0x11E60398);
+        process_chunked(&mut adler, b"Wikipedia", 4);
+        let mut unchecked = CheckedChecksum::<CRC32>::default();
+        process_chunked(&mut unchecked, b"Wikipedia", 4);
+    }
+
+    #[test]
+    fn mismatch() {
+        let mut crc = CheckedChecksum::<CRC32>::default().expect(0x12345678);
+        crc.process("Wikipedia".as_bytes(), &mut Vec::new())
+            .unwrap();
+        let err = crc.finish(&mut Vec::new()).unwrap_err();
+        let Error::ChecksumMismatch(mismatch) = err else {
+            panic!("Unexpected error {err:?}");
+        };
+        assert_eq!(
+            mismatch,
+            ChecksumMismatch {
+                expected: 0x12345678,
+                calculated: 0xadaac02e
+            }
+        );
+        assert_eq!(
+            Error::ChecksumMismatch(mismatch).to_string(),
+            "Checksum mismatch: expected 0x12345678, calculated 0xADAAC02E"
+        );
+    }
+
+    #[test]
+    fn finish_twice() {
+        let mut crc = CRC32::new();
+        crc.process("sqsh".as_bytes(), &mut Vec::new()).unwrap();
+        let checked = CheckedChecksum::new(CRC32::new()).expect(crc.checksum());
+        test_finish_twice(checked, "sqsh".as_bytes());
+    }
+}
@@ -0,0 +1,333 @@
+//! CRC16 and CRC64 checksums
+//!
+//! Table-driven implementation of the cyclic redundancy checks as described
+//! [here](https://en.wikipedia.org/wiki/Cyclic_redundancy_check). The
+//! variants are given by their parameters in the
+//! [catalogue](https://reveng.sourceforge.io/crc-catalogue/) notation:
+//! polynomial, initial value, reflection of input and output and the final
+//! XOR value.
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::core::{Checksum, Process, Reset};
+use log::{info, trace};
+
+/// Parameters of a CRC variant of up to 64 bits
+struct Params {
+    width: u32,
+    poly: u64,
+    init: u64,
+    reflected: bool,
+    xorout: u64,
+}
+
+/// Running CRC of a variant
+struct Model {
+    params: Params,
+    table: [u64; 256],
+    value: u64,
+}
+
+impl Model {
+    fn new(params: Params) -> Self {
+        let mut table = [0u64; 256];
+        let width = params.width;
+        let mask = u64::MAX >> (64 - width);
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = if params.reflected {
+                let poly = params.poly.reverse_bits() >> (64 - width);
+                (0..8).fold(i as u64, |v, _| match v & 1 {
+                    1 => (v >> 1) ^ poly,
+                    _ => v >> 1,
+                })
+            } else {
+                let top = 1 << (width - 1);
+                (0..8).fold((i as u64) << (width - 8), |v, _| match v & top {
+                    0 => (v << 1) & mask,
+                    _ => ((v << 1) ^ params.poly) & mask,
+                })
+            };
+        }
+        let value = params.init;
+        Model {
+            params,
+            table,
+            value,
+        }
+    }
+
+    fn update(&mut self, source: &[u8]) {
+        let width = self.params.width;
+        let mask = u64::MAX >> (64 - width);
+        for byte in source.iter() {
+            self.value = if self.params.reflected {
+                self.table[((self.value ^ *byte as u64) & 0xFF) as usize] ^ (self.value >> 8)
+            } else {
+                let index = ((self.value >> (width - 8)) ^ *byte as u64) & 0xFF;
+                (self.table[index as usize] ^ (self.value << 8)) & mask
+            };
+        }
+    }
+
+    fn sum(&self) -> u64 {
+        self.value ^ self.params.xorout
+    }
+
+    fn reset(&mut self) {
+        self.value = self.params.init;
+    }
+}
+
+/// Variants of the CRC16 checksum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crc16Variant {
+    /// CRC-16/CCITT-FALSE: polynomial `0x1021`, initial value `0xFFFF`
+    Ccitt,
+    /// CRC-16/MODBUS: reflected polynomial `0x8005`, initial value `0xFFFF`
+    Modbus,
+}
+
+impl Crc16Variant {
+    fn params(&self) -> Params {
+        match self {
+            Crc16Variant::Ccitt => Params {
+                width: 16,
+                poly: 0x1021,
+                init: 0xFFFF,
+                reflected: false,
+                xorout: 0,
+            },
+            Crc16Variant::Modbus => Params {
+                width: 16,
+                poly: 0x8005,
+                init: 0xFFFF,
+                reflected: true,
+                xorout: 0,
+            },
+        }
+    }
+}
+
+/// CRC16 struct to save the running CRC of the selected variant
+pub struct Crc16 {
+    model: Model,
+    finished: bool,
+}
+
+impl Crc16 {
+    /// Generate new CRC16 struct of the CCITT variant
+    pub fn new() -> Self {
+        Self::with_variant(Crc16Variant::Ccitt)
+    }
+
+    /// Generate new CRC16 struct of the given variant
+    pub fn with_variant(variant: Crc16Variant) -> Self {
+        info!("New CRC16 checksum created: {variant:?}");
+        Crc16 {
+            model: Model::new(variant.params()),
+            finished: false,
+        }
+    }
+}
+
+/// Use the new function for generating the default implementation
+impl Default for Crc16 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Crc16 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let csum = self.checksum();
+        write!(f, "CRC16<{csum:#06X}>")
+    }
+}
+
+impl Process for Crc16 {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> crate::Result<usize> {
+        self.model.update(source);
+        self.finished = false;
+        trace!("CRC16 Update w/: {source:#?}");
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> crate::Result<usize> {
+        if !self.finished {
+            let result = self.to_string();
+            sink.extend(result.as_bytes());
+            self.finished = true;
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for Crc16 {
+    fn reset(&mut self) {
+        self.model.reset();
+        self.finished = false;
+    }
+}
+
+impl Checksum for Crc16 {
+    type Output = u16;
+
+    fn checksum(&self) -> Self::Output {
+        self.model.sum() as u16
+    }
+}
+
+/// Variants of the CRC64 checksum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crc64Variant {
+    /// CRC-64/ECMA-182: polynomial `0x42F0E1EBA9EA3693`, initial value `0`
+    Ecma,
+    /// CRC-64/XZ: the reflected ECMA-182 polynomial with all bits of the
+    /// initial and final value set
+    Xz,
+}
+
+impl Crc64Variant {
+    fn params(&self) -> Params {
+        match self {
+            Crc64Variant::Ecma => Params {
+                width: 64,
+                poly: 0x42F0_E1EB_A9EA_3693,
+                init: 0,
+                reflected: false,
+                xorout: 0,
+            },
+            Crc64Variant::Xz => Params {
+                width: 64,
+                poly: 0x42F0_E1EB_A9EA_3693,
+                init: u64::MAX,
+                reflected: true,
+                xorout: u64::MAX,
+            },
+        }
+    }
+}
+
+/// CRC64 struct to save the running CRC of the selected variant
+pub struct Crc64 {
+    model: Model,
+    finished: bool,
+}
+
+impl Crc64 {
+    /// Generate new CRC64 struct of the ECMA variant
+    pub fn new() -> Self {
+        Self::with_variant(Crc64Variant::Ecma)
+    }
+
+    /// Generate new CRC64 struct of the given variant
+    pub fn with_variant(variant: Crc64Variant) -> Self {
+        info!("New CRC64 checksum created: {variant:?}");
+        Crc64 {
+            model: Model::new(variant.params()),
+            finished: false,
+        }
+    }
+}
+
+/// Use the new function for generating the default implementation
+impl Default for Crc64 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Crc64 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let csum = self.checksum();
+        write!(f, "CRC64<{csum:#018X}>")
+    }
+}
+
+impl Process for Crc64 {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> crate::Result<usize> {
+        self.model.update(source);
+        self.finished = false;
+        trace!("CRC64 Update w/: {source:#?}");
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> crate::Result<usize> {
+        if !self.finished {
+            let result = self.to_string();
+            sink.extend(result.as_bytes());
+            self.finished = true;
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for Crc64 {
+    fn reset(&mut self) {
+        self.model.reset();
+        self.finished = false;
+    }
+}
+
+impl Checksum for Crc64 {
+    type Output = u64;
+
+    fn checksum(&self) -> Self::Output {
+        self.model.sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::checksum::tests::*;
+    use crate::core::process::tests::{
+        process_chunked, test_deterministic_process, test_finish_twice,
+    };
+
+    const CHECK: &[u8] = b"123456789";
+
+    fn checksum<C: Checksum + Process>(mut checksum: C, source: &[u8]) -> C::Output {
+        process_chunked(&mut checksum, source, 4);
+        checksum.checksum()
+    }
+
+    #[test]
+    fn crc16() {
+        assert_eq!(checksum(Crc16::new(), CHECK), 0x29B1);
+        assert_eq!(
+            checksum(Crc16::with_variant(Crc16Variant::Modbus), CHECK),
+            0x4B37
+        );
+        assert_checksum::<u16, Crc16>("".as_bytes(), 0xFFFF);
+    }
+
+    #[test]
+    fn crc64() {
+        assert_eq!(checksum(Crc64::new(), CHECK), 0x6C40_DF5F_0B49_7347);
+        let xz = Crc64::with_variant(Crc64Variant::Xz);
+        assert_eq!(checksum(xz, CHECK), 0x995D_C9BB_DF19_39FA);
+        assert_checksum::<u64, Crc64>("".as_bytes(), 0);
+        // The XZ variant is the ECMA checksum of the `crc` crate
+        let source = "Awesome-string-baby".repeat(10).into_bytes();
+        let xz = Crc64::with_variant(Crc64Variant::Xz);
+        assert_eq!(checksum(xz, &source), crc::crc64::checksum_ecma(&source));
+    }
+
+    #[test]
+    fn formatting() {
+        check_display_format::<Crc16>("CRC16<0xFFFF>");
+        check_display_format::<Crc64>("CRC64<0x0000000000000000>");
+    }
+
+    #[test]
+    fn deterministic() {
+        test_deterministic_process::<Crc16>("Awesome-string-baby".as_bytes());
+        test_deterministic_process::<Crc64>("Awesome-string-baby".as_bytes());
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(Crc16::new(), "Wikipedia".as_bytes());
+        test_finish_twice(Crc64::new(), "Wikipedia".as_bytes());
+    }
+}
@@ -1,23 +1,33 @@
 //! CRC32 checksum
 //!
 //! Implementation of the CRC32 checksum algorithm as described [here](https://en.wikipedia.org/wiki/Cyclic_redundancy_check).
-use std::fmt::Display;
+use alloc::vec::Vec;
+use core::fmt::Display;
 
-use crate::core::{Checksum, Process};
+use crate::core::{Checksum, ChecksumOutput, Process, Reset};
 use crc::{crc32, Hasher32};
 use log::{info, trace};
 
 /// CRC32 struct to save inner Digest element from `crc32` crate
 pub struct CRC32 {
     a: crc32::Digest,
+    output: ChecksumOutput,
+    finished: bool,
 }
 
 impl CRC32 {
-    /// Generate new CRC32 struct
+    /// Generate new CRC32 struct writing the `Display` string in `finish`
     pub fn new() -> Self {
+        Self::with_output(ChecksumOutput::Text)
+    }
+
+    /// Generate new CRC32 struct writing the checksum in the given format
+    pub fn with_output(output: ChecksumOutput) -> Self {
         info!("New CRC32 checksum created");
         CRC32 {
             a: crc32::Digest::new(crc32::IEEE),
+            output,
+            finished: false,
         }
     }
 }
@@ -30,7 +40,7 @@ impl Default for CRC32 {
 }
 
 impl Display for CRC32 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let csum = self.a.sum32();
         write!(f, "CRC32<{csum:#010X}>")
     }
@@ -38,18 +48,28 @@ impl Display for CRC32 {
 
 /// Implementation of the Checksum trait for CRC32
 impl Process for CRC32 {
-    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> std::io::Result<usize> {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> crate::Result<usize> {
         self.a.write(source);
+        self.finished = false;
         trace!("CRC32 Update w/: {source:#?}");
         Ok(source.len())
     }
-    fn finish(&mut self, sink: &mut Vec<u8>) -> std::io::Result<usize> {
-        let result = self.to_string();
-        sink.extend(result.as_bytes());
+    fn finish(&mut self, sink: &mut Vec<u8>) -> crate::Result<usize> {
+        if !self.finished {
+            self.output.write(self, sink);
+            self.finished = true;
+        }
         Ok(0)
     }
 }
 
+impl Reset for CRC32 {
+    fn reset(&mut self) {
+        self.a.reset();
+        self.finished = false;
+    }
+}
+
 impl Checksum for CRC32 {
     type Output = u32;
 
@@ -62,6 +82,9 @@ impl Checksum for CRC32 {
 mod tests {
     use super::*;
     use crate::core::checksum::tests::*;
+    use crate::core::process::tests::{
+        process_chunked, test_deterministic_process, test_finish_twice,
+    };
 
     #[test]
     fn crc32() {
@@ -76,4 +99,26 @@ mod tests {
     fn formatting() {
         check_display_format::<CRC32>("CRC32<0x00000000>");
     }
+
+    #[test]
+    fn raw_output() {
+        let source = "Wikipedia".as_bytes();
+        let be = process_chunked(&mut CRC32::with_output(ChecksumOutput::RawBE), source, 4);
+        assert_eq!(u32::from_be_bytes(be.try_into().unwrap()), 0xadaac02e);
+        let le = process_chunked(&mut CRC32::with_output(ChecksumOutput::RawLE), source, 4);
+        assert_eq!(u32::from_le_bytes(le.try_into().unwrap()), 0xadaac02e);
+        let text = process_chunked(&mut CRC32::new(), source, 4);
+        assert_eq!(text, "CRC32<0xADAAC02E>".as_bytes());
+    }
+
+    #[test]
+    fn deterministic() {
+        test_deterministic_process::<CRC32>("Awesome-string-baby".as_bytes());
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(CRC32::new(), "Wikipedia".as_bytes());
+        test_finish_twice(CRC32::new(), &[]);
+    }
 }
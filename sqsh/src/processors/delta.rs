@@ -0,0 +1,339 @@
+//! # Delta
+//!
+//! Delta encoding of little-endian words. Each word is replaced by its
+//! difference to the previous word, the first word is compared against zero.
+//! Monotonic or slowly changing data, like timestamps, is turned into small
+//! values which are better suited for the following processors.
+//!
+//! The differences wrap around, so decreasing values are encoded without
+//! loss as well. Words may be split across `process` calls. A trailing
+//! partial word at the end of the stream is written unchanged.
+//!
+//! `SymbolDeltaEncoder` and `SymbolDeltaDecoder` apply the same coding to
+//! slices of integer symbols, so wider data needs no flattening to bytes.
+use super::words::Words;
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset, Symbol};
+use crate::Result;
+use alloc::vec::Vec;
+use log::info;
+
+/// Delta encoder for words of configurable width
+pub struct DeltaEncoder {
+    state: Words,
+}
+
+impl DeltaEncoder {
+    /// Create a new delta encoder for single bytes
+    pub fn new() -> Self {
+        Self::with_width(1)
+    }
+
+    /// Create a new delta encoder for little-endian words of `bytes` width
+    ///
+    /// Panics if the width is not 1, 2, 4 or 8.
+    pub fn with_width(bytes: usize) -> Self {
+        info!("New delta encoder with width {bytes}");
+        DeltaEncoder {
+            state: Words::new(bytes),
+        }
+    }
+}
+
+impl Default for DeltaEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for DeltaEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.state
+            .apply(source, sink, |word, last| (word.wrapping_sub(last), word));
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.finish(sink);
+        Ok(0)
+    }
+}
+
+impl Reset for DeltaEncoder {
+    fn reset(&mut self) {
+        self.state.reset();
+    }
+}
+
+impl Encoder for DeltaEncoder {}
+
+impl Invertible for DeltaEncoder {
+    type Decoder = DeltaDecoder;
+
+    fn inverse(&self) -> DeltaDecoder {
+        DeltaDecoder::with_width(self.state.width)
+    }
+}
+
+/// Delta decoder reconstructing the words of the `DeltaEncoder`
+pub struct DeltaDecoder {
+    state: Words,
+}
+
+impl DeltaDecoder {
+    /// Create a new delta decoder for single bytes
+    pub fn new() -> Self {
+        Self::with_width(1)
+    }
+
+    /// Create a new delta decoder for little-endian words of `bytes` width
+    ///
+    /// Panics if the width is not 1, 2, 4 or 8.
+    pub fn with_width(bytes: usize) -> Self {
+        info!("New delta decoder with width {bytes}");
+        DeltaDecoder {
+            state: Words::new(bytes),
+        }
+    }
+}
+
+impl Default for DeltaDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for DeltaDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        // Only the lower bytes of the state are written, so the carry into
+        // the upper bytes of the `u64` is irrelevant
+        self.state.apply(source, sink, |diff, last| {
+            let word = diff.wrapping_add(last);
+            (word, word)
+        });
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.finish(sink);
+        Ok(0)
+    }
+}
+
+impl Reset for DeltaDecoder {
+    fn reset(&mut self) {
+        self.state.reset();
+    }
+}
+
+impl Decoder for DeltaDecoder {
+    type Encoder = DeltaEncoder;
+}
+
+/// Decoder with the same word width as the encoder
+impl From<DeltaEncoder> for DeltaDecoder {
+    fn from(encoder: DeltaEncoder) -> Self {
+        encoder.inverse()
+    }
+}
+
+/// Delta encoder for integer symbols
+///
+/// ```
+/// use sqsh::core::Process;
+/// use sqsh::processors::SymbolDeltaEncoder;
+///
+/// let mut sink = Vec::new();
+/// let mut encoder = SymbolDeltaEncoder::<u16>::new();
+/// encoder.process(&[1000, 1010, 1005], &mut sink).unwrap();
+/// assert_eq!(sink, [1000, 10, u16::MAX - 4]);
+/// ```
+pub struct SymbolDeltaEncoder<S> {
+    last: S,
+}
+
+impl<S: Symbol> SymbolDeltaEncoder<S> {
+    /// Create a new delta encoder for symbols of type `S`
+    pub fn new() -> Self {
+        info!("New symbol delta encoder");
+        SymbolDeltaEncoder { last: S::default() }
+    }
+}
+
+impl<S: Symbol> Default for SymbolDeltaEncoder<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Symbol> Process<S> for SymbolDeltaEncoder<S> {
+    fn process(&mut self, source: &[S], sink: &mut Vec<S>) -> Result<usize> {
+        sink.extend(source.iter().map(|symbol| {
+            let diff = symbol.wrapping_sub(self.last);
+            self.last = *symbol;
+            diff
+        }));
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<S>) -> Result<usize> {
+        self.reset();
+        Ok(0)
+    }
+    fn max_output_size(&self, input_len: usize) -> Option<usize> {
+        Some(input_len)
+    }
+}
+
+impl<S: Symbol> Reset for SymbolDeltaEncoder<S> {
+    fn reset(&mut self) {
+        self.last = S::default();
+    }
+}
+
+impl<S: Symbol> Encoder<S> for SymbolDeltaEncoder<S> {}
+
+impl<S: Symbol> Invertible<S> for SymbolDeltaEncoder<S> {
+    type Decoder = SymbolDeltaDecoder<S>;
+
+    fn inverse(&self) -> SymbolDeltaDecoder<S> {
+        SymbolDeltaDecoder::new()
+    }
+}
+
+/// Delta decoder reconstructing the symbols of the `SymbolDeltaEncoder`
+pub struct SymbolDeltaDecoder<S> {
+    last: S,
+}
+
+impl<S: Symbol> SymbolDeltaDecoder<S> {
+    /// Create a new delta decoder for symbols of type `S`
+    pub fn new() -> Self {
+        info!("New symbol delta decoder");
+        SymbolDeltaDecoder { last: S::default() }
+    }
+}
+
+impl<S: Symbol> Default for SymbolDeltaDecoder<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Symbol> Process<S> for SymbolDeltaDecoder<S> {
+    fn process(&mut self, source: &[S], sink: &mut Vec<S>) -> Result<usize> {
+        sink.extend(source.iter().map(|diff| {
+            self.last = diff.wrapping_add(self.last);
+            self.last
+        }));
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<S>) -> Result<usize> {
+        self.reset();
+        Ok(0)
+    }
+    fn max_output_size(&self, input_len: usize) -> Option<usize> {
+        Some(input_len)
+    }
+}
+
+impl<S: Symbol> Reset for SymbolDeltaDecoder<S> {
+    fn reset(&mut self) {
+        self.last = S::default();
+    }
+}
+
+impl<S: Symbol> Decoder<S> for SymbolDeltaDecoder<S> {
+    type Encoder = SymbolDeltaEncoder<S>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    #[test]
+    fn bytes() {
+        test_buffered_process::<DeltaEncoder>(
+            &[1, 3, 6, 10, 9, 0, 255],
+            &[1, 2, 3, 4, 255, 247, 255],
+        );
+        test_buffered_process::<DeltaDecoder>(
+            &[1, 2, 3, 4, 255, 247, 255],
+            &[1, 3, 6, 10, 9, 0, 255],
+        );
+        test_roundtrip(DeltaEncoder::new(), "Awesome-string-baby".as_bytes());
+    }
+
+    #[test]
+    fn timestamps() {
+        let source: Vec<u8> = (0..100u32)
+            .map(|i| 1_660_000_000 + 60 * i)
+            .flat_map(|t| t.to_le_bytes())
+            .collect();
+        let encoded = test_roundtrip(DeltaEncoder::with_width(4), &source);
+        assert_eq!(encoded[..4], 1_660_000_000u32.to_le_bytes());
+        assert!(encoded[4..].chunks(4).all(|w| w == 60u32.to_le_bytes()));
+    }
+
+    #[test]
+    fn wrap_around() {
+        let source: Vec<u8> = [0u16, u16::MAX, 1, 0x8000, 0x7FFF, 0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let encoded = test_roundtrip(DeltaEncoder::with_width(2), &source);
+        assert_eq!(encoded[2..6], [0xFF, 0xFF, 0x02, 0x00]);
+        let wide: Vec<u8> = [u64::MAX, 0, 3]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        test_roundtrip(DeltaEncoder::with_width(8), &wide);
+    }
+
+    #[test]
+    fn partial_word() {
+        let encoded = test_roundtrip(DeltaEncoder::with_width(4), &[1, 0, 0, 0, 3, 0, 0, 0, 7, 7]);
+        assert_eq!(encoded, [1, 0, 0, 0, 2, 0, 0, 0, 7, 7]);
+    }
+
+    #[test]
+    fn from_encoder() {
+        let mut decoder = DeltaDecoder::from(DeltaEncoder::with_width(2));
+        let mut sink = Vec::new();
+        decoder.process(&[1, 0, 1, 0], &mut sink).unwrap();
+        assert_eq!(sink, [1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn symbols() {
+        let source: Vec<u16> = (0..1000).map(|i| 30_000 + (i * i % 97) as u16).collect();
+        let encoded = test_roundtrip(SymbolDeltaEncoder::new(), &source);
+        // Same coding as the byte encoder of the little-endian words
+        let bytes: Vec<u8> = source.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let expected = process_chunked(&mut DeltaEncoder::with_width(2), &bytes, 4096);
+        let flat: Vec<u8> = encoded.iter().flat_map(|v| v.to_le_bytes()).collect();
+        assert_eq!(flat, expected);
+
+        let signed = [i32::MIN, i32::MAX, -1, 0, 1];
+        let encoded = test_roundtrip(SymbolDeltaEncoder::new(), &signed);
+        assert_eq!(encoded, [i32::MIN, -1, i32::MIN, 1, 1]);
+        assert!(test_roundtrip(SymbolDeltaEncoder::<u64>::new(), &[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_width() {
+        DeltaEncoder::with_width(3);
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<DeltaEncoder>(&source);
+        test_deterministic_process::<DeltaDecoder>(&source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(DeltaEncoder::with_width(2), "Wikipedia".as_bytes());
+        test_finish_twice(DeltaDecoder::with_width(2), "Wikipedia".as_bytes());
+        test_finish_twice(SymbolDeltaEncoder::new(), &[1u16, 2, 3]);
+    }
+}
@@ -1,5 +1,6 @@
-use crate::core::Process;
-use std::io::Result as IOResult;
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::Result;
+use alloc::vec::Vec;
 
 /// Duplicate all data from the source to the sink (copy).
 pub struct Duplicate {}
@@ -17,13 +18,35 @@ impl Default for Duplicate {
 }
 
 impl Process for Duplicate {
-    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> IOResult<usize> {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
         sink.extend(source);
         Ok(source.len())
     }
-    fn finish(&mut self, _: &mut Vec<u8>) -> IOResult<usize> {
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
         Ok(0)
     }
+    fn max_output_size(&self, input_len: usize) -> Option<usize> {
+        Some(input_len)
+    }
+}
+
+impl Reset for Duplicate {
+    fn reset(&mut self) {}
+}
+
+/// Duplication is its own inverse
+impl Encoder for Duplicate {}
+
+impl Decoder for Duplicate {
+    type Encoder = Duplicate;
+}
+
+impl Invertible for Duplicate {
+    type Decoder = Duplicate;
+
+    fn inverse(&self) -> Duplicate {
+        Duplicate::new()
+    }
 }
 
 #[cfg(test)]
@@ -40,4 +63,14 @@ mod tests {
         );
         test_buffered_process::<Duplicate>("This is great".as_bytes(), "This is great".as_bytes());
     }
+
+    #[test]
+    fn deterministic() {
+        test_deterministic_process::<Duplicate>("Awesome-string-baby".as_bytes());
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(Duplicate::new(), "Wikipedia".as_bytes());
+    }
 }
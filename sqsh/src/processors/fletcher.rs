@@ -0,0 +1,229 @@
+//! # Fletcher
+//!
+//! Implementation of the Fletcher-16 and Fletcher-32 checksum algorithms as
+//! described [here](https://en.wikipedia.org/wiki/Fletcher%27s_checksum).
+//!
+//! Fletcher-16 sums bytes modulo 255, Fletcher-32 sums little-endian 16-bit
+//! words modulo 65535. A trailing odd byte of Fletcher-32 is padded with a
+//! zero high byte.
+use crate::core::{Checksum, Process, Reset};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::Display;
+use log::{info, trace};
+
+/// Fletcher-16 struct to save both running sums
+#[derive(Debug)]
+pub struct Fletcher16 {
+    sum1: u16,
+    sum2: u16,
+    finished: bool,
+}
+
+impl Fletcher16 {
+    /// Generate new Fletcher16 struct
+    pub fn new() -> Self {
+        info!("New Fletcher16 checksum");
+        Fletcher16 {
+            sum1: 0,
+            sum2: 0,
+            finished: false,
+        }
+    }
+}
+
+impl Checksum for Fletcher16 {
+    type Output = u16;
+
+    fn checksum(&self) -> u16 {
+        (self.sum2 << 8) | self.sum1
+    }
+}
+
+/// Use the new function for generating the default implementation
+impl Default for Fletcher16 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Printing should display the checksum
+impl Display for Fletcher16 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let csum = self.checksum();
+        write!(f, "Fletcher16<{csum:#06X}>")
+    }
+}
+
+impl Process for Fletcher16 {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> crate::Result<usize> {
+        for byte in source.iter() {
+            self.sum1 = (self.sum1 + *byte as u16) % 255;
+            self.sum2 = (self.sum2 + self.sum1) % 255;
+        }
+        trace!("Fletcher16 Update, New State: {self:?}");
+        self.finished = false;
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> crate::Result<usize> {
+        if !self.finished {
+            let result = self.to_string();
+            sink.extend(result.as_bytes());
+            self.finished = true;
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for Fletcher16 {
+    fn reset(&mut self) {
+        self.sum1 = 0;
+        self.sum2 = 0;
+        self.finished = false;
+    }
+}
+
+/// Fletcher-32 struct to save both running sums and a pending odd byte
+#[derive(Debug)]
+pub struct Fletcher32 {
+    sum1: u32,
+    sum2: u32,
+    pending: Option<u8>,
+    finished: bool,
+}
+
+impl Fletcher32 {
+    /// Generate new Fletcher32 struct
+    pub fn new() -> Self {
+        info!("New Fletcher32 checksum");
+        Fletcher32 {
+            sum1: 0,
+            sum2: 0,
+            pending: None,
+            finished: false,
+        }
+    }
+
+    /// Running sums after adding `word`
+    fn add(sums: (u32, u32), word: u16) -> (u32, u32) {
+        let sum1 = (sums.0 + word as u32) % 65535;
+        (sum1, (sums.1 + sum1) % 65535)
+    }
+}
+
+impl Checksum for Fletcher32 {
+    type Output = u32;
+
+    fn checksum(&self) -> u32 {
+        let mut sums = (self.sum1, self.sum2);
+        if let Some(byte) = self.pending {
+            sums = Self::add(sums, byte as u16);
+        }
+        (sums.1 << 16) | sums.0
+    }
+}
+
+/// Use the new function for generating the default implementation
+impl Default for Fletcher32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Printing should display the checksum
+impl Display for Fletcher32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let csum = self.checksum();
+        write!(f, "Fletcher32<{csum:#010X}>")
+    }
+}
+
+impl Process for Fletcher32 {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> crate::Result<usize> {
+        for byte in source.iter() {
+            match self.pending.take() {
+                Some(low) => {
+                    let word = u16::from_le_bytes([low, *byte]);
+                    (self.sum1, self.sum2) = Self::add((self.sum1, self.sum2), word);
+                }
+                None => self.pending = Some(*byte),
+            }
+        }
+        trace!("Fletcher32 Update, New State: {self:?}");
+        self.finished = false;
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> crate::Result<usize> {
+        if !self.finished {
+            let result = self.to_string();
+            sink.extend(result.as_bytes());
+            self.finished = true;
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for Fletcher32 {
+    fn reset(&mut self) {
+        self.sum1 = 0;
+        self.sum2 = 0;
+        self.pending = None;
+        self.finished = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::checksum::tests::*;
+    use crate::core::process::tests::{
+        process_chunked, test_deterministic_process, test_finish_twice,
+    };
+
+    #[test]
+    fn fletcher16() {
+        assert_checksum::<u16, Fletcher16>("abcde".as_bytes(), 0xC8F0);
+        assert_checksum::<u16, Fletcher16>("abcdef".as_bytes(), 0x2057);
+        assert_checksum::<u16, Fletcher16>("abcdefgh".as_bytes(), 0x0627);
+        assert_checksum::<u16, Fletcher16>("".as_bytes(), 0x0000);
+        // Sums of 255 wrap around to zero
+        assert_checksum::<u16, Fletcher16>(&[0xFF; 1000], 0x0000);
+    }
+
+    #[test]
+    fn fletcher32() {
+        assert_checksum::<u32, Fletcher32>("abcde".as_bytes(), 0xF04FC729);
+        assert_checksum::<u32, Fletcher32>("abcdef".as_bytes(), 0x56502D2A);
+        assert_checksum::<u32, Fletcher32>("abcdefgh".as_bytes(), 0xEBE19591);
+        assert_checksum::<u32, Fletcher32>("".as_bytes(), 0x00000000);
+        assert_checksum::<u32, Fletcher32>(&[0xFF; 1000], 0x00000000);
+    }
+
+    #[test]
+    fn split_words() {
+        for chunk_size in [1, 3, 4096] {
+            let mut checksum = Fletcher32::new();
+            let output = process_chunked(&mut checksum, "abcde".as_bytes(), chunk_size);
+            assert_eq!(output, "Fletcher32<0xF04FC729>".as_bytes());
+        }
+    }
+
+    #[test]
+    fn formatting() {
+        check_debug_format::<Fletcher16>("Fletcher16 { sum1: 0, sum2: 0, finished: false }");
+        check_display_format::<Fletcher16>("Fletcher16<0x0000>");
+        check_display_format::<Fletcher32>("Fletcher32<0x00000000>");
+    }
+
+    #[test]
+    fn deterministic() {
+        test_deterministic_process::<Fletcher16>("Awesome-string-baby".as_bytes());
+        test_deterministic_process::<Fletcher32>("Awesome-string-baby".as_bytes());
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(Fletcher16::new(), "Wikipedia".as_bytes());
+        test_finish_twice(Fletcher32::new(), "Wikipedia".as_bytes());
+    }
+}
@@ -0,0 +1,213 @@
+//! # Elias Gamma
+//!
+//! Universal code for positive integers as described
+//! [here](https://en.wikipedia.org/wiki/Elias_gamma_coding). Small values
+//! get short codes, so the code suits run lengths and ranks.
+//!
+//! Gamma codes can not represent zero, so every byte `b` is coded as the
+//! integer `b + 1`. The integer `n` with `N + 1` significant bits is written
+//! MSB-first as `N` zeros followed by the `N + 1` bits of `n`, i.e. a byte
+//! takes between 1 and 17 bits.
+//!
+//! The last code is padded with zeros to a complete byte. As every code
+//! contains a one bit, the padding is never mistaken for a code.
+use crate::core::bits::{BitReader, BitWriter};
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use alloc::format;
+use alloc::vec::Vec;
+use log::info;
+
+/// Elias gamma encoder of bytes offset by one
+pub struct EliasGammaEncoder {
+    writer: BitWriter,
+}
+
+impl EliasGammaEncoder {
+    /// Create a new Elias gamma encoder
+    pub fn new() -> Self {
+        info!("New Elias gamma encoder");
+        EliasGammaEncoder {
+            writer: BitWriter::new(),
+        }
+    }
+}
+
+impl Default for EliasGammaEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for EliasGammaEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            let value = *byte as u64 + 1;
+            let bits = u64::BITS - value.leading_zeros();
+            self.writer.write(0, bits - 1, sink);
+            self.writer.write(value, bits, sink);
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        self.writer.finish(sink);
+        Ok(0)
+    }
+    fn max_output_size(&self, input_len: usize) -> Option<usize> {
+        // 17 bits per byte and the pending bits of the previous call
+        Some((input_len * 17).div_ceil(8) + 1)
+    }
+}
+
+impl Reset for EliasGammaEncoder {
+    fn reset(&mut self) {
+        self.writer.reset();
+    }
+}
+
+impl Encoder for EliasGammaEncoder {}
+
+impl Invertible for EliasGammaEncoder {
+    type Decoder = EliasGammaDecoder;
+
+    fn inverse(&self) -> EliasGammaDecoder {
+        EliasGammaDecoder::new()
+    }
+}
+
+/// Elias gamma decoder restoring the bytes of the `EliasGammaEncoder`
+pub struct EliasGammaDecoder {
+    reader: BitReader,
+    /// Number of leading zeros of the current code
+    zeros: u32,
+    /// Whether the leading one of the current code was read
+    prefix_done: bool,
+}
+
+impl EliasGammaDecoder {
+    /// Create a new Elias gamma decoder
+    pub fn new() -> Self {
+        info!("New Elias gamma decoder");
+        EliasGammaDecoder {
+            reader: BitReader::new(),
+            zeros: 0,
+            prefix_done: false,
+        }
+    }
+}
+
+impl Default for EliasGammaDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for EliasGammaDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            self.reader.push(*byte);
+            loop {
+                if !self.prefix_done {
+                    match self.reader.read(1) {
+                        Some(0) => self.zeros += 1,
+                        Some(_) => self.prefix_done = true,
+                        None => break,
+                    }
+                    if self.zeros > 8 {
+                        return Err(Error::invalid_code("Elias gamma code exceeds a byte"));
+                    }
+                    continue;
+                }
+                let Some(rest) = self.reader.read(self.zeros) else {
+                    break;
+                };
+                let value = (1 << self.zeros) | rest;
+                if value > 256 {
+                    let msg = format!("Elias gamma value {value} exceeds a byte");
+                    return Err(Error::invalid_code(msg));
+                }
+                sink.push((value - 1) as u8);
+                self.zeros = 0;
+                self.prefix_done = false;
+            }
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        // Up to 7 zeros are the padding of the last byte
+        let truncated = self.prefix_done || self.zeros >= 8;
+        self.reset();
+        if truncated {
+            return Err(Error::truncated("Truncated Elias gamma code"));
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for EliasGammaDecoder {
+    fn reset(&mut self) {
+        self.reader.reset();
+        self.zeros = 0;
+        self.prefix_done = false;
+    }
+}
+
+impl Decoder for EliasGammaDecoder {
+    type Encoder = EliasGammaEncoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    #[test]
+    fn all_bytes() {
+        let source: Vec<u8> = (0..=u8::MAX).collect();
+        test_roundtrip(EliasGammaEncoder::new(), &source);
+        let source: Vec<u8> = (0..=u8::MAX).rev().cycle().take(3000).collect();
+        test_roundtrip(EliasGammaEncoder::new(), &source);
+    }
+
+    #[test]
+    fn code_lengths() {
+        // 0 -> 1, 1 -> 010, 2 -> 011, 3 -> 00100
+        let encoded = test_roundtrip(EliasGammaEncoder::new(), &[0, 1, 2, 3]);
+        assert_eq!(encoded, [0b1010_0110, 0b0100_0000]);
+        let encoded = test_roundtrip(EliasGammaEncoder::new(), &[0; 80]);
+        assert_eq!(encoded, [0xFF; 10]);
+        assert_eq!(test_roundtrip(EliasGammaEncoder::new(), &[255]).len(), 3);
+        assert!(test_roundtrip(EliasGammaEncoder::new(), &[]).is_empty());
+        let encoder = EliasGammaEncoder::new();
+        assert_eq!(encoder.max_output_size(1), Some(4));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut decoder = EliasGammaDecoder::new();
+        let err = decoder.process(&[0, 0x40], &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+        let mut decoder = EliasGammaDecoder::new();
+        let err = decoder
+            .process(&[0, 0x80, 0x80], &mut Vec::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+
+        let mut decoder = EliasGammaDecoder::new();
+        decoder.process(&[0b0000_0010], &mut Vec::new()).unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<EliasGammaEncoder>(&source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(EliasGammaEncoder::new(), &[1, 2, 3]);
+        test_finish_twice(EliasGammaDecoder::new(), &[0b1010_0110, 0b0100_0000]);
+    }
+}
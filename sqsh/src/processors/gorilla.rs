@@ -0,0 +1,369 @@
+//! # Gorilla
+//!
+//! XOR compression of `f64` time series as described in the paper
+//! [Gorilla: A Fast, Scalable, In-Memory Time Series Database](https://www.vldb.org/pvldb/vol8/p1816-teller.pdf).
+//!
+//! The source is read as little-endian `f64` values. Each value is XORed
+//! with its predecessor (the first one with zero) and written MSB-first:
+//!
+//! - `0`: the value equals its predecessor
+//! - `10`: the meaningful bits of the XOR fit into the window of leading and
+//!   trailing zeros of the previous `11` value, only the window is written
+//! - `11`: 5 bits count of leading zeros (at most 31), 6 bits count of
+//!   meaningful bits (`0` meaning 64) and the meaningful bits
+//!
+//! The stream ends with the marker `11 11111 000000`, which can not occur as
+//! a value, followed by 3 bits count of trailing bytes not forming a complete
+//! value and these bytes. The output is padded with zeros to a complete
+//! byte. An empty source produces an empty output.
+use crate::core::bits::BitWriter;
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use alloc::format;
+use alloc::vec::Vec;
+use log::{debug, info};
+
+/// Size of a value in bytes
+const VALUE_SIZE: usize = 8;
+/// Largest count of leading zeros which can be stored
+const MAX_LEADING: u32 = 31;
+/// Control bits `11`, 31 leading zeros and 64 meaningful bits
+const END_MARKER: u64 = 0b1_1111_1100_0000;
+
+/// Write all 64 bits of `value`
+fn write_u64(writer: &mut BitWriter, value: u64, bits: u32, sink: &mut Vec<u8>) {
+    if bits > 32 {
+        writer.write(value >> 32, bits - 32, sink);
+        writer.write(value & 0xFFFF_FFFF, 32, sink);
+    } else {
+        writer.write(value, bits, sink);
+    }
+}
+
+/// Gorilla encoder for little-endian `f64` values
+pub struct GorillaF64Encoder {
+    writer: BitWriter,
+    partial: Vec<u8>,
+    previous: u64,
+    window: Option<(u32, u32)>,
+    started: bool,
+}
+
+impl GorillaF64Encoder {
+    /// Create a new Gorilla encoder
+    pub fn new() -> Self {
+        info!("New Gorilla f64 encoder");
+        GorillaF64Encoder {
+            writer: BitWriter::new(),
+            partial: Vec::with_capacity(VALUE_SIZE),
+            previous: 0,
+            window: None,
+            started: false,
+        }
+    }
+
+    fn encode(&mut self, value: u64, sink: &mut Vec<u8>) {
+        let xor = value ^ self.previous;
+        self.previous = value;
+        if xor == 0 {
+            self.writer.write(0, 1, sink);
+            return;
+        }
+        let leading = xor.leading_zeros().min(MAX_LEADING);
+        let trailing = xor.trailing_zeros();
+        match self.window {
+            Some((l, t)) if leading >= l && trailing >= t => {
+                self.writer.write(0b10, 2, sink);
+                write_u64(&mut self.writer, xor >> t, 64 - l - t, sink);
+            }
+            _ => {
+                let meaningful = 64 - leading - trailing;
+                self.writer.write(0b11, 2, sink);
+                self.writer.write(leading as u64, 5, sink);
+                self.writer.write((meaningful & 0x3F) as u64, 6, sink);
+                write_u64(&mut self.writer, xor >> trailing, meaningful, sink);
+                self.window = Some((leading, trailing));
+            }
+        }
+    }
+}
+
+impl Default for GorillaF64Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for GorillaF64Encoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.started |= !source.is_empty();
+        for byte in source.iter() {
+            self.partial.push(*byte);
+            if self.partial.len() == VALUE_SIZE {
+                let value = u64::from_le_bytes(self.partial[..].try_into().expect("8 bytes"));
+                self.partial.clear();
+                self.encode(value, sink);
+            }
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if self.started {
+            debug!(
+                "Gorilla end marker with {} trailing bytes",
+                self.partial.len()
+            );
+            self.writer.write(END_MARKER, 13, sink);
+            self.writer.write(self.partial.len() as u64, 3, sink);
+            for byte in self.partial.iter() {
+                self.writer.write(*byte as u64, 8, sink);
+            }
+            self.writer.finish(sink);
+        }
+        self.reset();
+        Ok(0)
+    }
+}
+
+impl Reset for GorillaF64Encoder {
+    fn reset(&mut self) {
+        self.writer.reset();
+        self.partial.clear();
+        self.previous = 0;
+        self.window = None;
+        self.started = false;
+    }
+}
+
+impl Encoder for GorillaF64Encoder {}
+
+impl Invertible for GorillaF64Encoder {
+    type Decoder = GorillaF64Decoder;
+
+    fn inverse(&self) -> GorillaF64Decoder {
+        GorillaF64Decoder::new()
+    }
+}
+
+/// Result of decoding a single item of the bit stream
+enum Item {
+    Value(u64),
+    End(Vec<u8>),
+}
+
+/// Cursor reading single bits from a byte slice
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Cursor<'_> {
+    fn read(&mut self, bits: u32) -> Option<u64> {
+        if self.position + bits as usize > self.data.len() * 8 {
+            return None;
+        }
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let byte = self.data[self.position / 8];
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u64;
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+/// Gorilla decoder restoring the little-endian `f64` values
+pub struct GorillaF64Decoder {
+    data: Vec<u8>,
+    position: usize,
+    previous: u64,
+    window: Option<(u32, u32)>,
+}
+
+impl GorillaF64Decoder {
+    /// Create a new Gorilla decoder
+    pub fn new() -> Self {
+        info!("New Gorilla f64 decoder");
+        GorillaF64Decoder {
+            data: Vec::new(),
+            position: 0,
+            previous: 0,
+            window: None,
+        }
+    }
+
+    /// Decode the next item, `None` if more data is needed
+    fn next_item(&mut self) -> Result<Option<Item>> {
+        let mut cursor = Cursor {
+            data: &self.data,
+            position: self.position,
+        };
+        let mut window = self.window;
+        macro_rules! read {
+            ($bits:expr) => {
+                match cursor.read($bits) {
+                    Some(value) => value,
+                    None => return Ok(None),
+                }
+            };
+        }
+        let xor = if read!(1) == 0 {
+            0
+        } else if read!(1) == 0 {
+            let (l, t) =
+                window.ok_or_else(|| Error::invalid_code("Gorilla window before first value"))?;
+            read!(64 - l - t) << t
+        } else {
+            let leading = read!(5) as u32;
+            let meaningful = match read!(6) as u32 {
+                0 => 64,
+                m => m,
+            };
+            if leading == MAX_LEADING && meaningful == 64 {
+                let count = read!(3) as usize;
+                let mut trailing = Vec::with_capacity(count);
+                for _ in 0..count {
+                    trailing.push(read!(8) as u8);
+                }
+                // Skip the padding to the next byte
+                self.position = cursor.position.div_ceil(8) * 8;
+                return Ok(Some(Item::End(trailing)));
+            }
+            if leading + meaningful > 64 {
+                let msg = format!("Invalid Gorilla window: {leading} + {meaningful}");
+                return Err(Error::invalid_code(msg));
+            }
+            let trailing = 64 - leading - meaningful;
+            window = Some((leading, trailing));
+            read!(meaningful) << trailing
+        };
+        self.position = cursor.position;
+        self.window = window;
+        self.previous ^= xor;
+        Ok(Some(Item::Value(self.previous)))
+    }
+}
+
+impl Default for GorillaF64Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for GorillaF64Decoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.data.extend(source);
+        while let Some(item) = self.next_item()? {
+            match item {
+                Item::Value(value) => sink.extend(value.to_le_bytes()),
+                Item::End(trailing) => {
+                    sink.extend(trailing);
+                    self.previous = 0;
+                    self.window = None;
+                }
+            }
+        }
+        let consumed = self.position / 8;
+        self.data.drain(..consumed);
+        self.position -= consumed * 8;
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        let incomplete = !self.data.is_empty();
+        self.reset();
+        if incomplete {
+            return Err(Error::truncated("Missing Gorilla end marker"));
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for GorillaF64Decoder {
+    fn reset(&mut self) {
+        self.data.clear();
+        self.position = 0;
+        self.previous = 0;
+        self.window = None;
+    }
+}
+
+impl Decoder for GorillaF64Decoder {
+    type Encoder = GorillaF64Encoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    fn bytes(values: &[f64]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn time_series() {
+        let values: Vec<f64> = (0..2000).map(|i| 20.0 + (i / 10) as f64 * 0.5).collect();
+        let source = bytes(&values);
+        let encoded = test_roundtrip(GorillaF64Encoder::new(), &source);
+        assert!(encoded.len() < source.len() / 8);
+    }
+
+    #[test]
+    fn constant() {
+        let source = bytes(&[12.5; 800]);
+        let encoded = test_roundtrip(GorillaF64Encoder::new(), &source);
+        // 12.5 has 1 leading and 48 trailing zeros, so the first value takes
+        // 13 + 15 bits, the repetitions 799 bits and the end marker 16 bits
+        assert_eq!(encoded.len(), (13 + 15 + 799 + 16usize).div_ceil(8));
+    }
+
+    #[test]
+    fn special_values() {
+        let nan = f64::from_bits(0x7FF8_0000_DEAD_BEEF);
+        let values = [
+            0.0,
+            -0.0,
+            f64::INFINITY,
+            nan,
+            f64::NAN,
+            -nan,
+            f64::MIN,
+            1e-310,
+        ];
+        let source = bytes(&values);
+        test_roundtrip(GorillaF64Encoder::new(), &source);
+        let random: Vec<u8> = (0..4000u64)
+            .flat_map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15).to_le_bytes())
+            .collect();
+        test_roundtrip(GorillaF64Encoder::new(), &random);
+    }
+
+    #[test]
+    fn partial_value() {
+        let mut source = bytes(&[1.0, 2.0, 3.0]);
+        source.extend([1, 2, 3]);
+        test_roundtrip(GorillaF64Encoder::new(), &source);
+        test_roundtrip(GorillaF64Encoder::new(), &[7, 7, 7, 7, 7, 7, 7]);
+        assert!(test_roundtrip(GorillaF64Encoder::new(), &[]).is_empty());
+        test_finish_twice(GorillaF64Encoder::new(), &source);
+    }
+
+    #[test]
+    fn truncated() {
+        let source = bytes(&[1.0, 2.0, 3.0]);
+        let encoded = process_chunked(&mut GorillaF64Encoder::new(), &source, 8);
+        let mut decoder = GorillaF64Decoder::new();
+        decoder
+            .process(&encoded[..encoded.len() - 2], &mut Vec::new())
+            .unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+    }
+
+    #[test]
+    fn deterministic() {
+        let values: Vec<f64> = (0..1000).map(|i| (i as f64 / 50.0).sin()).collect();
+        test_deterministic_process::<GorillaF64Encoder>(&bytes(&values));
+    }
+}
@@ -0,0 +1,466 @@
+//! # Huffman
+//!
+//! Implementation of canonical Huffman coding as described
+//! [here](https://en.wikipedia.org/wiki/Canonical_Huffman_code).
+//!
+//! The encoder works in block mode: the whole source is buffered and the
+//! code is built from a `ProbTable` of the block in `finish`. Code lengths
+//! are limited to 15 bits. The output consists of the number of encoded
+//! symbols (`u64`, little-endian), the code lengths of all 256 byte values
+//! as 4-bit nibbles (128 bytes, high nibble first), and the bit-packed
+//! payload padded with zeros to a complete byte. An empty source produces an
+//! empty output.
+//!
+//! With a static table the code is built from a given `ProbTable` upfront.
+//! Encoder and decoder share the table, so the code lengths are omitted from
+//! the output. Only symbols contained in the static table can be encoded.
+use crate::core::bits::BitWriter;
+use crate::core::{Decoder, Encoder, Invertible, ProbTable, Process, Reset};
+use crate::{Error, Result};
+use log::{debug, info};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Maximum length of a code in bits
+const MAX_LENGTH: usize = 15;
+/// Size of the symbol count in bytes
+const COUNT_SIZE: usize = 8;
+/// Size of the code length table in bytes
+const LENGTHS_SIZE: usize = 128;
+
+/// Code lengths of a Huffman tree over the given `(symbol, count)` pairs
+fn tree_lengths(weights: &[(u8, usize)]) -> [u8; 256] {
+    let mut lengths = [0u8; 256];
+    if let [(symbol, _)] = weights {
+        lengths[*symbol as usize] = 1;
+        return lengths;
+    }
+    let mut parents: Vec<usize> = vec![0; weights.len()];
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> = weights
+        .iter()
+        .enumerate()
+        .map(|(id, (_, count))| Reverse((*count, id)))
+        .collect();
+    while let (Some(Reverse((a, i))), Some(Reverse((b, j)))) = (heap.pop(), heap.pop()) {
+        let id = parents.len();
+        parents.push(id);
+        parents[i] = id;
+        parents[j] = id;
+        heap.push(Reverse((a + b, id)));
+    }
+    for (leaf, (symbol, _)) in weights.iter().enumerate() {
+        let mut node = leaf;
+        while parents[node] != node {
+            node = parents[node];
+            lengths[*symbol as usize] += 1;
+        }
+    }
+    lengths
+}
+
+/// Canonical Huffman code over bytes
+#[derive(Clone)]
+struct Code {
+    lengths: [u8; 256],
+    codes: [u16; 256],
+    /// First code of every length
+    first: [u32; MAX_LENGTH + 1],
+    /// Number of codes of every length
+    counts: [usize; MAX_LENGTH + 1],
+    /// Index into `symbols` of the first code of every length
+    offsets: [usize; MAX_LENGTH + 1],
+    /// Symbols ordered by code
+    symbols: Vec<u8>,
+}
+
+impl Code {
+    /// Build a length-limited code from the counts of `table`
+    ///
+    /// If the tree exceeds the maximum length, the counts are halved until
+    /// the code fits.
+    fn from_table(table: &ProbTable<u8>) -> Self {
+        let mut weights: Vec<(u8, usize)> = (0..=u8::MAX)
+            .filter_map(|b| table.count(&b).map(|c| (b, c)))
+            .collect();
+        loop {
+            let lengths = tree_lengths(&weights);
+            if lengths.iter().all(|l| *l as usize <= MAX_LENGTH) {
+                return Self::from_lengths(lengths).expect("Huffman tree is a valid code");
+            }
+            debug!("Huffman code too long, halving counts");
+            weights.iter_mut().for_each(|(_, c)| *c = c.div_ceil(2));
+        }
+    }
+
+    /// Assign canonical codes to the given code lengths
+    fn from_lengths(lengths: [u8; 256]) -> Result<Self> {
+        let mut counts = [0; MAX_LENGTH + 1];
+        for length in lengths.iter().filter(|l| **l > 0) {
+            if *length as usize > MAX_LENGTH {
+                let msg = format!("Invalid Huffman code length: {length}");
+                return Err(Error::invalid_code(msg));
+            }
+            counts[*length as usize] += 1;
+        }
+        let kraft: usize = (1..=MAX_LENGTH)
+            .map(|l| counts[l] << (MAX_LENGTH - l))
+            .sum();
+        if kraft > 1 << MAX_LENGTH {
+            return Err(Error::invalid_code("Oversubscribed Huffman code lengths"));
+        }
+        let mut first = [0; MAX_LENGTH + 1];
+        let mut offsets = [0; MAX_LENGTH + 1];
+        let mut code = 0u32;
+        let mut offset = 0;
+        for length in 1..=MAX_LENGTH {
+            first[length] = code;
+            offsets[length] = offset;
+            code = (code + counts[length] as u32) << 1;
+            offset += counts[length];
+        }
+        let mut symbols: Vec<u8> = (0..=u8::MAX).filter(|b| lengths[*b as usize] > 0).collect();
+        symbols.sort_by_key(|b| lengths[*b as usize]);
+        let mut codes = [0u16; 256];
+        let mut next = first;
+        for symbol in symbols.iter() {
+            let length = lengths[*symbol as usize] as usize;
+            codes[*symbol as usize] = next[length] as u16;
+            next[length] += 1;
+        }
+        Ok(Code {
+            lengths,
+            codes,
+            first,
+            counts,
+            offsets,
+            symbols,
+        })
+    }
+
+    /// Parse the code lengths table of the header
+    fn from_header(header: &[u8]) -> Result<Self> {
+        let mut lengths = [0u8; 256];
+        for (i, byte) in header.iter().enumerate() {
+            lengths[2 * i] = byte >> 4;
+            lengths[2 * i + 1] = byte & 0x0F;
+        }
+        Self::from_lengths(lengths)
+    }
+
+    /// Write the code lengths table of the header
+    fn write_header(&self, sink: &mut Vec<u8>) {
+        sink.extend(self.lengths.chunks(2).map(|l| (l[0] << 4) | l[1]));
+    }
+
+    /// Look up the symbol of `code` with the given length
+    fn symbol(&self, code: u32, length: usize) -> Option<u8> {
+        let index = code.checked_sub(self.first[length])? as usize;
+        (index < self.counts[length]).then(|| self.symbols[self.offsets[length] + index])
+    }
+}
+
+/// Huffman encoder building a canonical code per block
+pub struct HuffmanEncoder {
+    data: Vec<u8>,
+    code: Option<Code>,
+}
+
+impl HuffmanEncoder {
+    /// Create a new encoder building the code from each block
+    pub fn new() -> Self {
+        info!("New Huffman encoder");
+        HuffmanEncoder {
+            data: Vec::new(),
+            code: None,
+        }
+    }
+
+    /// Create a new encoder using the code of a trained `table`
+    ///
+    /// The code lengths are not written to the output, the decoder needs to
+    /// be created with the same table.
+    pub fn with_static_table(table: ProbTable<u8>) -> Self {
+        info!(
+            "New Huffman encoder with {} static symbols",
+            table.members()
+        );
+        HuffmanEncoder {
+            data: Vec::new(),
+            code: Some(Code::from_table(&table)),
+        }
+    }
+}
+
+impl Default for HuffmanEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for HuffmanEncoder {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> Result<usize> {
+        self.data.extend(source);
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if self.data.is_empty() {
+            return Ok(0);
+        }
+        let data = std::mem::take(&mut self.data);
+        sink.extend((data.len() as u64).to_le_bytes());
+        let code = match self.code.as_ref() {
+            Some(code) => code.clone(),
+            None => {
+                let table: ProbTable<u8> = data.iter().copied().collect();
+                let code = Code::from_table(&table);
+                code.write_header(sink);
+                code
+            }
+        };
+        let mut writer = BitWriter::new();
+        for byte in data.iter() {
+            let length = code.lengths[*byte as usize];
+            if length == 0 {
+                let msg = format!("Symbol not in static Huffman table: {byte}");
+                return Err(Error::invalid_code(msg));
+            }
+            writer.write(code.codes[*byte as usize] as u64, length as u32, sink);
+        }
+        writer.finish(sink);
+        Ok(0)
+    }
+}
+
+impl Reset for HuffmanEncoder {
+    fn reset(&mut self) {
+        self.data.clear();
+    }
+}
+
+impl Encoder for HuffmanEncoder {}
+
+impl Invertible for HuffmanEncoder {
+    type Decoder = HuffmanDecoder;
+
+    fn inverse(&self) -> HuffmanDecoder {
+        HuffmanDecoder {
+            static_code: self.code.clone(),
+            ..HuffmanDecoder::new()
+        }
+    }
+}
+
+/// State of the block currently being decoded
+struct Block {
+    code: Code,
+    remaining: u64,
+    value: u32,
+    length: usize,
+}
+
+/// Huffman decoder restoring the blocks of the `HuffmanEncoder`
+pub struct HuffmanDecoder {
+    static_code: Option<Code>,
+    header: Vec<u8>,
+    block: Option<Block>,
+}
+
+impl HuffmanDecoder {
+    /// Create a new decoder reading the code from each block
+    pub fn new() -> Self {
+        info!("New Huffman decoder");
+        HuffmanDecoder {
+            static_code: None,
+            header: Vec::new(),
+            block: None,
+        }
+    }
+
+    /// Create a new decoder using the code of a trained `table`
+    pub fn with_static_table(table: ProbTable<u8>) -> Self {
+        info!(
+            "New Huffman decoder with {} static symbols",
+            table.members()
+        );
+        HuffmanDecoder {
+            static_code: Some(Code::from_table(&table)),
+            ..Self::new()
+        }
+    }
+
+    /// Collect the header and start a new block once it is complete
+    fn read_header(&mut self, byte: u8) -> Result<()> {
+        self.header.push(byte);
+        let size = match self.static_code {
+            Some(_) => COUNT_SIZE,
+            None => COUNT_SIZE + LENGTHS_SIZE,
+        };
+        if self.header.len() < size {
+            return Ok(());
+        }
+        let count = u64::from_le_bytes(self.header[..COUNT_SIZE].try_into().expect("8 bytes"));
+        if count == 0 {
+            return Err(Error::invalid_code("Empty Huffman block"));
+        }
+        let code = match self.static_code.as_ref() {
+            Some(code) => code.clone(),
+            None => Code::from_header(&self.header[COUNT_SIZE..])?,
+        };
+        debug!("Decode Huffman block of {count} symbols");
+        self.header.clear();
+        self.block = Some(Block {
+            code,
+            remaining: count,
+            value: 0,
+            length: 0,
+        });
+        Ok(())
+    }
+}
+
+impl Default for HuffmanDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for HuffmanDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            let Some(block) = self.block.as_mut() else {
+                self.read_header(*byte)?;
+                continue;
+            };
+            for shift in (0..8).rev() {
+                block.value = (block.value << 1) | ((byte >> shift) & 1) as u32;
+                block.length += 1;
+                if block.length > MAX_LENGTH {
+                    return Err(Error::invalid_code("Invalid Huffman code"));
+                }
+                if let Some(symbol) = block.code.symbol(block.value, block.length) {
+                    sink.push(symbol);
+                    block.value = 0;
+                    block.length = 0;
+                    block.remaining -= 1;
+                    if block.remaining == 0 {
+                        // The remaining bits of the byte are padding
+                        self.block = None;
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        let incomplete = self.block.is_some() || !self.header.is_empty();
+        self.reset();
+        if incomplete {
+            return Err(Error::truncated("Truncated Huffman block"));
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for HuffmanDecoder {
+    fn reset(&mut self) {
+        self.header.clear();
+        self.block = None;
+    }
+}
+
+impl Decoder for HuffmanDecoder {
+    type Encoder = HuffmanEncoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    const HEADER_SIZE: usize = COUNT_SIZE + LENGTHS_SIZE;
+
+    #[test]
+    fn empty() {
+        assert!(test_roundtrip(HuffmanEncoder::new(), &[]).is_empty());
+    }
+
+    #[test]
+    fn single_symbol() {
+        let encoded = test_roundtrip(HuffmanEncoder::new(), &[7; 1000]);
+        assert_eq!(encoded[..COUNT_SIZE], 1000u64.to_le_bytes());
+        assert_eq!(encoded.len(), HEADER_SIZE + 125);
+        test_roundtrip(HuffmanEncoder::new(), &[42]);
+    }
+
+    #[test]
+    fn text() {
+        let source = "Awesome-string-baby".repeat(100).into_bytes();
+        let encoded = test_roundtrip(HuffmanEncoder::new(), &source);
+        assert!(encoded.len() < HEADER_SIZE + source.len() / 2);
+        let all: Vec<u8> = (0..=255).collect();
+        test_roundtrip(HuffmanEncoder::new(), &all);
+    }
+
+    #[test]
+    fn length_limit() {
+        // Fibonacci counts produce a maximally skewed tree
+        let (mut a, mut b) = (1, 1);
+        let mut source = Vec::new();
+        let mut table = ProbTable::new();
+        for symbol in 0..20u8 {
+            source.extend(std::iter::repeat_n(symbol, a));
+            (a, b) = (b, a + b);
+        }
+        table.feed(source.iter().copied());
+        let code = Code::from_table(&table);
+        assert!(code.lengths.iter().all(|l| *l as usize <= MAX_LENGTH));
+        test_roundtrip(HuffmanEncoder::new(), &source);
+    }
+
+    #[test]
+    fn static_table() {
+        let mut table = ProbTable::new();
+        table.feed("Awesome-string-baby".bytes());
+        let source = "baby-string-awesome".repeat(10).into_bytes();
+        let encoded = test_roundtrip(HuffmanEncoder::with_static_table(table.clone()), &source);
+        assert_eq!(encoded[..COUNT_SIZE], (source.len() as u64).to_le_bytes());
+        assert!(encoded.len() < COUNT_SIZE + source.len());
+
+        let mut decoder = HuffmanDecoder::with_static_table(table.clone());
+        let decoded = process_chunked(&mut decoder, &encoded, 5);
+        assert_eq!(decoded, source);
+
+        let mut encoder = HuffmanEncoder::with_static_table(table);
+        encoder.process("xyz".as_bytes(), &mut Vec::new()).unwrap();
+        let err = encoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+    }
+
+    #[test]
+    fn truncated() {
+        let mut encoder = HuffmanEncoder::new();
+        let mut encoded = Vec::new();
+        encoder.process("sqsh".as_bytes(), &mut encoded).unwrap();
+        encoder.finish(&mut encoded).unwrap();
+        let mut decoder = HuffmanDecoder::new();
+        decoder
+            .process(&encoded[..encoded.len() - 1], &mut Vec::new())
+            .unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<HuffmanEncoder>(&source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(HuffmanEncoder::new(), "Wikipedia".as_bytes());
+        let encoded = process_chunked(&mut HuffmanEncoder::new(), "Wikipedia".as_bytes(), 4);
+        test_finish_twice(HuffmanDecoder::new(), &encoded);
+    }
+}
@@ -0,0 +1,369 @@
+//! # LZW
+//!
+//! Implementation of the Lempel-Ziv-Welch algorithm as described
+//! [here](https://en.wikipedia.org/wiki/Lempel%E2%80%93Ziv%E2%80%93Welch).
+//!
+//! The dictionary starts with the 256 single byte strings. Code `256` is the
+//! clear code, new strings are assigned the codes from `257` onwards. Codes
+//! are written MSB-first with the smallest width able to represent the next
+//! free code, starting with 9 bits and growing up to `max_bits`. Once the
+//! dictionary is full the encoder writes the clear code and both sides start
+//! over with the initial dictionary.
+//!
+//! The last code is padded with zeros to a complete byte. As every code is
+//! wider than 8 bits the padding is never mistaken for a code.
+use crate::core::bits::{BitReader, BitWriter};
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use log::{debug, info};
+use std::collections::HashMap;
+
+/// Code resetting the dictionary
+const CLEAR: u16 = 256;
+/// First code assigned to a new string
+const FIRST: u16 = 257;
+/// Width of the codes of the initial dictionary
+const MIN_BITS: u32 = 9;
+/// Default maximum code width
+const DEFAULT_MAX_BITS: u32 = 12;
+
+/// Width of the codes while `next` is the next free code
+fn width(next: usize, max_bits: u32) -> u32 {
+    (usize::BITS - next.leading_zeros()).clamp(MIN_BITS, max_bits)
+}
+
+fn check_max_bits(max_bits: u32) {
+    assert!(
+        (MIN_BITS..=16).contains(&max_bits),
+        "Maximum code width must be between 9 and 16 bits"
+    );
+}
+
+/// LZW encoder with a dictionary of up to `2^max_bits` codes
+pub struct LzwEncoder {
+    max_bits: u32,
+    dictionary: HashMap<(u16, u8), u16>,
+    next: usize,
+    prefix: Option<u16>,
+    writer: BitWriter,
+}
+
+impl LzwEncoder {
+    /// Create a new encoder with a maximum code width of 12 bits
+    pub fn new() -> Self {
+        Self::with_max_bits(DEFAULT_MAX_BITS)
+    }
+
+    /// Create a new encoder for a compression level from 1 to 9
+    ///
+    /// Level `l` allows codes of `8 + l` bits, levels 8 and 9 both use the
+    /// largest width of 16 bits. Larger dictionaries take longer until they
+    /// are full but capture more repetitions.
+    ///
+    /// Panics if `level` is not between 1 and 9.
+    pub fn with_level(level: u32) -> Self {
+        assert!((1..=9).contains(&level), "Level must be between 1 and 9");
+        Self::with_max_bits((8 + level).min(16))
+    }
+
+    /// Create a new encoder with codes of at most `max_bits` bits
+    ///
+    /// Panics if `max_bits` is not between 9 and 16.
+    pub fn with_max_bits(max_bits: u32) -> Self {
+        check_max_bits(max_bits);
+        info!("New LZW encoder with maximum code width {max_bits}");
+        LzwEncoder {
+            max_bits,
+            dictionary: HashMap::new(),
+            next: FIRST as usize,
+            prefix: None,
+            writer: BitWriter::new(),
+        }
+    }
+
+    fn write(&mut self, code: u16, sink: &mut Vec<u8>) {
+        let bits = width(self.next, self.max_bits);
+        self.writer.write(code as u64, bits, sink);
+    }
+}
+
+impl Default for LzwEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for LzwEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            let Some(prefix) = self.prefix else {
+                self.prefix = Some(*byte as u16);
+                continue;
+            };
+            if let Some(code) = self.dictionary.get(&(prefix, *byte)) {
+                self.prefix = Some(*code);
+                continue;
+            }
+            self.write(prefix, sink);
+            if self.next < 1 << self.max_bits {
+                self.dictionary.insert((prefix, *byte), self.next as u16);
+                self.next += 1;
+            } else {
+                debug!("LZW dictionary full, clearing");
+                self.write(CLEAR, sink);
+                self.dictionary.clear();
+                self.next = FIRST as usize;
+            }
+            self.prefix = Some(*byte as u16);
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if let Some(prefix) = self.prefix {
+            self.write(prefix, sink);
+        }
+        self.writer.finish(sink);
+        self.reset();
+        Ok(0)
+    }
+}
+
+impl Reset for LzwEncoder {
+    fn reset(&mut self) {
+        self.dictionary.clear();
+        self.next = FIRST as usize;
+        self.prefix = None;
+        self.writer.reset();
+    }
+}
+
+impl Encoder for LzwEncoder {}
+
+impl Invertible for LzwEncoder {
+    type Decoder = LzwDecoder;
+
+    fn inverse(&self) -> LzwDecoder {
+        LzwDecoder::with_max_bits(self.max_bits)
+    }
+}
+
+/// LZW decoder rebuilding the dictionary of the `LzwEncoder`
+pub struct LzwDecoder {
+    max_bits: u32,
+    /// Prefix code and last byte of every string, indexed by code
+    dictionary: Vec<(u16, u8)>,
+    previous: Option<u16>,
+    reader: BitReader,
+    string: Vec<u8>,
+}
+
+impl LzwDecoder {
+    /// Create a new decoder with a maximum code width of 12 bits
+    pub fn new() -> Self {
+        Self::with_max_bits(DEFAULT_MAX_BITS)
+    }
+
+    /// Create a new decoder with codes of at most `max_bits` bits
+    ///
+    /// Panics if `max_bits` is not between 9 and 16.
+    pub fn with_max_bits(max_bits: u32) -> Self {
+        check_max_bits(max_bits);
+        info!("New LZW decoder with maximum code width {max_bits}");
+        let mut decoder = LzwDecoder {
+            max_bits,
+            dictionary: Vec::with_capacity(1 << max_bits),
+            previous: None,
+            reader: BitReader::new(),
+            string: Vec::new(),
+        };
+        decoder.reset();
+        decoder
+    }
+
+    /// Width of the next code
+    ///
+    /// The decoder adds each string one code later than the encoder, so
+    /// the pending string is taken into account.
+    fn width(&self) -> u32 {
+        let full = self.dictionary.len() == 1 << self.max_bits;
+        let pending = (self.previous.is_some() && !full) as usize;
+        width(self.dictionary.len() + pending, self.max_bits)
+    }
+
+    /// Write the string of `code` into `self.string`
+    fn expand(&mut self, mut code: u16) {
+        self.string.clear();
+        while code > CLEAR {
+            let (prefix, byte) = self.dictionary[code as usize];
+            self.string.push(byte);
+            code = prefix;
+        }
+        self.string.push(code as u8);
+        self.string.reverse();
+    }
+
+    fn decode(&mut self, code: u16, sink: &mut Vec<u8>) -> Result<()> {
+        if code == CLEAR {
+            if self.previous.is_none() {
+                return Err(Error::invalid_code("Unexpected LZW clear code"));
+            }
+            debug!("LZW dictionary cleared");
+            self.dictionary.truncate(FIRST as usize);
+            self.previous = None;
+            return Ok(());
+        }
+        let next = self.dictionary.len();
+        match self.previous {
+            None if code < CLEAR => self.expand(code),
+            Some(previous) if (code as usize) < next => {
+                self.expand(code);
+                if next < 1 << self.max_bits {
+                    self.dictionary.push((previous, self.string[0]));
+                }
+            }
+            // The KwKwK case: the code is the string being defined
+            Some(previous) if code as usize == next && next < 1 << self.max_bits => {
+                self.expand(previous);
+                let first = self.string[0];
+                self.string.push(first);
+                self.dictionary.push((previous, first));
+            }
+            _ => {
+                let msg = format!("Invalid LZW code: {code}");
+                return Err(Error::invalid_code(msg));
+            }
+        }
+        sink.extend(&self.string);
+        self.previous = Some(code);
+        Ok(())
+    }
+}
+
+impl Default for LzwDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for LzwDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            self.reader.push(*byte);
+            while let Some(code) = self.reader.read(self.width()) {
+                self.decode(code as u16, sink)?;
+            }
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        let (pending, remainder) = (self.reader.pending(), self.reader.remainder());
+        self.reset();
+        if pending >= 8 {
+            return Err(Error::truncated("Truncated LZW code"));
+        }
+        if remainder != 0 {
+            return Err(Error::invalid_code("Invalid LZW padding"));
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for LzwDecoder {
+    fn reset(&mut self) {
+        self.dictionary.clear();
+        self.dictionary.extend((0..=u8::MAX).map(|b| (0, b)));
+        // The clear code has no string
+        self.dictionary.push((0, 0));
+        self.previous = None;
+        self.reader.reset();
+    }
+}
+
+impl Decoder for LzwDecoder {
+    type Encoder = LzwEncoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    /// Pseudo-random bytes of a xorshift generator
+    fn random(len: usize) -> Vec<u8> {
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn text() {
+        let source = "TOBEORNOTTOBEORTOBEORNOT".repeat(50).into_bytes();
+        let encoded = test_roundtrip(LzwEncoder::new(), &source);
+        assert!(encoded.len() < source.len() / 4);
+    }
+
+    #[test]
+    fn random_bytes() {
+        let source = random(20_000);
+        for max_bits in [9, 10, 12, 16] {
+            test_roundtrip(LzwEncoder::with_max_bits(max_bits), &source);
+        }
+    }
+
+    #[test]
+    fn levels() {
+        let words = ["sqsh", "compression", "of", "scientific", "data", "streams"];
+        let mut state = 1u32;
+        let source: Vec<u8> = (0..30_000)
+            .flat_map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                words[(state >> 16) as usize % words.len()]
+                    .bytes()
+                    .chain([b' '])
+            })
+            .collect();
+        let sizes: Vec<usize> = (1..=9)
+            .map(|level| test_roundtrip(LzwEncoder::with_level(level), &source).len())
+            .collect();
+        assert!(sizes.windows(2).all(|w| w[1] <= w[0]), "{sizes:?}");
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<LzwEncoder>(&source);
+    }
+
+    #[test]
+    fn kwkwk() {
+        test_roundtrip(LzwEncoder::new(), "abababababababab".as_bytes());
+        test_roundtrip(LzwEncoder::with_max_bits(9), &[7; 50_000]);
+    }
+
+    #[test]
+    fn edge_cases() {
+        assert!(test_roundtrip(LzwEncoder::new(), &[]).is_empty());
+        let encoded = test_roundtrip(LzwEncoder::new(), &[42]);
+        assert_eq!(encoded, [42 >> 1, 0]);
+        test_finish_twice(LzwEncoder::new(), "Wikipedia".as_bytes());
+    }
+
+    #[test]
+    fn invalid() {
+        // Code 300 is not defined before any string was added
+        let mut decoder = LzwDecoder::new();
+        let err = decoder.process(&[0x96, 0x00], &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+
+        let mut decoder = LzwDecoder::new();
+        decoder.process(&[0x15, 0x01], &mut Vec::new()).unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+    }
+}
@@ -3,11 +3,69 @@
 //! Processors are consuming the data stream from the source and writing
 //! some output to the sink. All submodules are implementing some kind of
 //! processors which implement the `crate::core::Process` trait.
+//!
+//! All processors build without the `std` feature except for the ones built
+//! on hashing or time: `ArithmeticEncoder`, `HuffmanEncoder` and `LzwEncoder`
+//! with their decoders, and `best_of_within`.
 mod adler32;
+#[cfg(feature = "std")]
+mod arithmetic;
+mod batch;
+mod bitpack;
+#[cfg(feature = "std")]
+mod budget;
+mod bwt;
+mod checked;
+mod crc;
 mod crc32;
+mod delta;
 mod duplicate;
+mod fletcher;
+mod gamma;
+mod gorilla;
+#[cfg(feature = "std")]
+mod huffman;
+#[cfg(feature = "std")]
+mod lzw;
+mod mtf;
+mod npy;
+pub mod prelude;
+mod refdelta;
+mod remap;
+mod rice;
+mod shrink_or_bypass;
+mod shuffle;
+mod transpose;
+mod words;
+mod zigzag;
 
 // Reexport processors on this level
 pub use adler32::Adler32;
+#[cfg(feature = "std")]
+pub use arithmetic::{ArithmeticDecoder, ArithmeticEncoder};
+pub use batch::{BatchCompressor, BatchStats};
+pub use bitpack::{BitPack, BitUnpack};
+#[cfg(feature = "std")]
+pub use budget::{best_of_within, BestOf};
+pub use bwt::{BwtDecoder, BwtEncoder};
+pub use checked::CheckedChecksum;
+pub use crc::{Crc16, Crc16Variant, Crc64, Crc64Variant};
 pub use crc32::CRC32;
+pub use delta::{DeltaDecoder, DeltaEncoder, SymbolDeltaDecoder, SymbolDeltaEncoder};
 pub use duplicate::Duplicate;
+pub use fletcher::{Fletcher16, Fletcher32};
+pub use gamma::{EliasGammaDecoder, EliasGammaEncoder};
+pub use gorilla::{GorillaF64Decoder, GorillaF64Encoder};
+#[cfg(feature = "std")]
+pub use huffman::{HuffmanDecoder, HuffmanEncoder};
+#[cfg(feature = "std")]
+pub use lzw::{LzwDecoder, LzwEncoder};
+pub use mtf::{MoveToFrontDecoder, MoveToFrontEncoder};
+pub use npy::{Npy, NpyHeader};
+pub use refdelta::{RefDeltaDecoder, RefDeltaEncoder};
+pub use remap::{RemapDecoder, RemapEncoder};
+pub use rice::{RiceDecoder, RiceEncoder};
+pub use shrink_or_bypass::{ShrinkOrBypass, ShrinkOrBypassDecoder};
+pub use shuffle::{ByteShuffle, ByteUnshuffle};
+pub use transpose::{MatrixTransposeDecoder, MatrixTransposeEncoder};
+pub use zigzag::{ZigZagDecoder, ZigZagEncoder};
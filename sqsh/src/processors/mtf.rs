@@ -0,0 +1,173 @@
+//! # Move-to-Front
+//!
+//! Implementation of the move-to-front transform as described
+//! [here](https://en.wikipedia.org/wiki/Move-to-front_transform).
+//!
+//! Each byte is replaced by its index in a list of all byte values, after
+//! which the byte is moved to the front of the list. Recently used bytes get
+//! small indices, so repetitions turn into runs of zeros. The output is
+//! well suited for run-length encoding.
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::Result;
+use alloc::vec::Vec;
+use log::{info, trace};
+
+/// Initial alphabet ordered by byte value
+fn alphabet() -> Vec<u8> {
+    (0..=u8::MAX).collect()
+}
+
+/// Move-to-front encoder emitting the index of each byte
+pub struct MoveToFrontEncoder {
+    alphabet: Vec<u8>,
+}
+
+impl MoveToFrontEncoder {
+    /// Create a new move-to-front encoder
+    pub fn new() -> Self {
+        info!("New move-to-front encoder");
+        MoveToFrontEncoder {
+            alphabet: alphabet(),
+        }
+    }
+}
+
+impl Default for MoveToFrontEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for MoveToFrontEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            let index = self
+                .alphabet
+                .iter()
+                .position(|b| b == byte)
+                .expect("Alphabet contains all bytes");
+            self.alphabet[..=index].rotate_right(1);
+            sink.push(index as u8);
+            trace!("Move-to-front: {byte} -> {index}");
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        self.reset();
+        Ok(0)
+    }
+}
+
+impl Reset for MoveToFrontEncoder {
+    fn reset(&mut self) {
+        self.alphabet = alphabet();
+    }
+}
+
+impl Encoder for MoveToFrontEncoder {}
+
+impl Invertible for MoveToFrontEncoder {
+    type Decoder = MoveToFrontDecoder;
+
+    fn inverse(&self) -> MoveToFrontDecoder {
+        MoveToFrontDecoder::new()
+    }
+}
+
+/// Move-to-front decoder restoring the bytes from their indices
+pub struct MoveToFrontDecoder {
+    alphabet: Vec<u8>,
+}
+
+impl MoveToFrontDecoder {
+    /// Create a new move-to-front decoder
+    pub fn new() -> Self {
+        info!("New move-to-front decoder");
+        MoveToFrontDecoder {
+            alphabet: alphabet(),
+        }
+    }
+}
+
+impl Default for MoveToFrontDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for MoveToFrontDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for index in source.iter() {
+            let index = *index as usize;
+            let byte = self.alphabet[index];
+            self.alphabet[..=index].rotate_right(1);
+            sink.push(byte);
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        self.reset();
+        Ok(0)
+    }
+}
+
+impl Reset for MoveToFrontDecoder {
+    fn reset(&mut self) {
+        self.alphabet = alphabet();
+    }
+}
+
+impl Decoder for MoveToFrontDecoder {
+    type Encoder = MoveToFrontEncoder;
+}
+
+impl From<MoveToFrontEncoder> for MoveToFrontDecoder {
+    fn from(encoder: MoveToFrontEncoder) -> Self {
+        encoder.inverse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    #[test]
+    fn move_to_front() {
+        test_buffered_process::<MoveToFrontEncoder>(
+            "bananaaa".as_bytes(),
+            &[98, 98, 110, 1, 1, 1, 0, 0],
+        );
+        test_buffered_process::<MoveToFrontDecoder>(
+            &[98, 98, 110, 1, 1, 1, 0, 0],
+            "bananaaa".as_bytes(),
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        test_roundtrip(MoveToFrontEncoder::new(), "Awesome-string-baby".as_bytes());
+        let all: Vec<u8> = (0..=255).rev().chain(0..=255).collect();
+        let encoded = test_roundtrip(MoveToFrontEncoder::new(), &all);
+        assert_eq!(encoded[256..], (0..=255).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn runs_become_zeros() {
+        let encoded = test_roundtrip(MoveToFrontEncoder::new(), "aaaabbbbaaaa".as_bytes());
+        assert_eq!(encoded, [97, 0, 0, 0, 98, 0, 0, 0, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<MoveToFrontEncoder>(&source);
+        test_deterministic_process::<MoveToFrontDecoder>(&source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(MoveToFrontEncoder::new(), "Wikipedia".as_bytes());
+        test_finish_twice(MoveToFrontDecoder::new(), "Wikipedia".as_bytes());
+    }
+}
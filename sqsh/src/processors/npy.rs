@@ -0,0 +1,406 @@
+//! # NumPy
+//!
+//! Support for NumPy `.npy` files as described
+//! [here](https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html).
+//!
+//! The header of the file is parsed and passed through unchanged, only the
+//! data section is handed to an inner processor. The inner processor is
+//! created from the parsed header, so it can be configured according to the
+//! data type and element size of the array. As the header is never altered,
+//! the same processor is used for both directions: wrap an encoder for
+//! compression and the matching decoder for decompression.
+//!
+//! `Npy::float_encoder` and `Npy::float_decoder` configure a built-in
+//! pipeline: `f4` and `f8` arrays are byte shuffled and delta coded, the
+//! data of all other dtypes is passed through unchanged.
+use crate::core::{Pipeline, PipelineBuilder, Process};
+use crate::processors::{ByteShuffle, DeltaEncoder};
+use crate::{Error, Result};
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use log::{debug, info};
+
+/// Magic string at the start of every `.npy` file
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Parsed header of a `.npy` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NpyHeader {
+    /// Data type description, e.g. `<f8`, or the field list of a
+    /// structured dtype
+    pub descr: String,
+    /// Whether the data is stored in column-major order
+    pub fortran_order: bool,
+    /// Shape of the array
+    pub shape: Vec<usize>,
+    /// Size of a single element in bytes
+    pub element_size: usize,
+    /// Length of the whole header (including magic and version) in bytes
+    pub len: usize,
+}
+
+impl NpyHeader {
+    /// Parse the header from the start of `data`
+    ///
+    /// Returns `None` if `data` does not yet contain the complete header.
+    pub fn parse(data: &[u8]) -> Result<Option<Self>> {
+        let prefix = data.len().min(MAGIC.len());
+        if data[..prefix] != MAGIC[..prefix] {
+            return Err(Error::invalid_code("Missing .npy magic string"));
+        }
+        if data.len() < MAGIC.len() + 2 {
+            return Ok(None);
+        }
+        let (size_len, start) = match data[MAGIC.len()] {
+            1 => (2, MAGIC.len() + 4),
+            2 | 3 => (4, MAGIC.len() + 6),
+            v => {
+                return Err(Error::invalid_code(format!(
+                    "Unsupported .npy version: {v}"
+                )))
+            }
+        };
+        if data.len() < start {
+            return Ok(None);
+        }
+        let mut size = [0u8; 4];
+        size[..size_len].copy_from_slice(&data[MAGIC.len() + 2..start]);
+        let len = start + u32::from_le_bytes(size) as usize;
+        if data.len() < len {
+            return Ok(None);
+        }
+        let dict = core::str::from_utf8(&data[start..len])
+            .map_err(|_| Error::invalid_code("Header of .npy is not valid text"))?;
+        let descr = value_of(dict, "descr")?
+            .trim_matches(|c| c == '\'' || c == '"')
+            .to_string();
+        let element_size = match descr.starts_with('[') {
+            true => structured_size(&descr)?,
+            false => dtype_size(&descr)?,
+        };
+        let fortran_order = match value_of(dict, "fortran_order")? {
+            "True" => true,
+            "False" => false,
+            v => return Err(Error::invalid_code(format!("Invalid fortran_order: {v}"))),
+        };
+        let shape = value_of(dict, "shape")?
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| Error::invalid_code("Invalid .npy shape"))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        Ok(Some(NpyHeader {
+            descr,
+            fortran_order,
+            shape,
+            element_size,
+            len,
+        }))
+    }
+}
+
+/// Size in bytes of a simple dtype like `<f8`, `<U10` or `<M8[ns]`
+fn dtype_size(descr: &str) -> Result<usize> {
+    let unsupported = || Error::invalid_code(format!("Unsupported .npy dtype: {descr}"));
+    let mut chars = descr.trim_start_matches(['<', '>', '|', '=']).chars();
+    let kind = chars.next().ok_or_else(unsupported)?;
+    let rest = chars.as_str();
+    let digits = match kind {
+        // Datetimes and timedeltas carry their unit, e.g. `[ns]`
+        'M' | 'm' => rest.split('[').next().unwrap_or_default(),
+        _ => rest,
+    };
+    let size: usize = digits.parse().map_err(|_| unsupported())?;
+    match kind {
+        // Unicode strings are stored as UCS-4
+        'U' => Ok(size * 4),
+        'b' | 'i' | 'u' | 'f' | 'c' | 'S' | 'a' | 'V' | 'M' | 'm' => Ok(size),
+        _ => Err(unsupported()),
+    }
+}
+
+/// Size in bytes of a structured dtype like `[('x', '<f8'), ('y', '<i4', (3,))]`
+///
+/// Nested structured dtypes are not supported.
+fn structured_size(descr: &str) -> Result<usize> {
+    let unsupported = || Error::invalid_code(format!("Unsupported .npy dtype: {descr}"));
+    let fields = descr
+        .strip_prefix('[')
+        .and_then(|d| d.strip_suffix(']'))
+        .ok_or_else(unsupported)?;
+    let mut size = 0;
+    for field in split_top_level(fields) {
+        let field = field
+            .strip_prefix('(')
+            .and_then(|f| f.strip_suffix(')'))
+            .ok_or_else(unsupported)?;
+        let parts = split_top_level(field);
+        let dtype = parts.get(1).ok_or_else(unsupported)?;
+        if !dtype.starts_with(['\'', '"']) {
+            return Err(unsupported());
+        }
+        let mut count = 1;
+        if let Some(shape) = parts.get(2) {
+            let shape = shape.trim_matches(['(', ')']);
+            for dim in split_top_level(shape) {
+                count *= dim.parse::<usize>().map_err(|_| unsupported())?;
+            }
+        }
+        size += dtype_size(dtype.trim_matches(['\'', '"']))? * count;
+    }
+    Ok(size)
+}
+
+/// Split at the commas outside of brackets and quotes, dropping empty parts
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut start) = (0usize, None, 0);
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+/// Extract the raw value of `key` from the header dictionary
+fn value_of<'a>(dict: &'a str, key: &str) -> Result<&'a str> {
+    let missing = || Error::invalid_code(format!("Missing key in .npy header: {key}"));
+    let pattern = format!("'{key}':");
+    let start = dict.find(&pattern).ok_or_else(missing)? + pattern.len();
+    let rest = dict[start..].trim_start();
+    let end = match rest.chars().next() {
+        Some('(') => rest.find(')').map(|i| i + 1),
+        Some('[') => split_top_level(rest).first().map(|field| field.len()),
+        Some(q @ ('\'' | '"')) => rest[1..].find(q).map(|i| i + 2),
+        _ => rest.find([',', '}']),
+    }
+    .ok_or_else(missing)?;
+    Ok(rest[..end].trim_matches(|c| c == '(' || c == ')'))
+}
+
+/// Pass the `.npy` header through and process the data with an inner processor
+///
+/// The inner processor is created by `configure` as soon as the header is
+/// complete.
+pub struct Npy<F, P> {
+    configure: F,
+    header: Vec<u8>,
+    inner: Option<P>,
+}
+
+impl<F: FnMut(&NpyHeader) -> P, P: Process> Npy<F, P> {
+    /// Create a new processor configuring its inner processor with `configure`
+    pub fn new(configure: F) -> Self {
+        info!("New NumPy processor");
+        Npy {
+            configure,
+            header: Vec::new(),
+            inner: None,
+        }
+    }
+}
+
+/// Built-in stages for the data section described by `header`
+fn float_stages(header: &NpyHeader) -> PipelineBuilder {
+    let builder = Pipeline::builder();
+    match header.descr.trim_start_matches(['<', '>', '=']) {
+        "f4" | "f8" => builder
+            .add(ByteShuffle::new(header.element_size))
+            .add(DeltaEncoder::with_width(header.element_size)),
+        _ => builder,
+    }
+}
+
+impl Npy<fn(&NpyHeader) -> Pipeline, Pipeline> {
+    /// Create a new processor byte shuffling and delta coding float arrays
+    pub fn float_encoder() -> Self {
+        Npy::new(|header: &NpyHeader| float_stages(header).build())
+    }
+
+    /// Create a new processor restoring the output of `float_encoder`
+    pub fn float_decoder() -> Self {
+        Npy::new(|header: &NpyHeader| float_stages(header).decoder_pipeline())
+    }
+}
+
+impl<F: FnMut(&NpyHeader) -> P, P: Process> Process for Npy<F, P> {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.process(source, sink)?;
+            return Ok(source.len());
+        }
+        self.header.extend(source);
+        if let Some(header) = NpyHeader::parse(&self.header)? {
+            debug!("Parsed .npy header: {header:?}");
+            let mut inner = (self.configure)(&header);
+            sink.extend(&self.header[..header.len]);
+            inner.process(&self.header[header.len..], sink)?;
+            self.header.clear();
+            self.inner = Some(inner);
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        match self.inner.take() {
+            Some(mut inner) => inner.finish(sink),
+            None if self.header.is_empty() => Ok(0),
+            None => Err(Error::truncated("Incomplete .npy header")),
+        }
+    }
+    /// Flush the inner processor, an incomplete header is held back
+    fn flush(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(sink),
+            None => Ok(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::{process_chunked, test_finish_twice, HoldBack};
+    use crate::processors::{Duplicate, ShrinkOrBypass, ShrinkOrBypassDecoder};
+
+    /// Build a version 1.0 `.npy` file with the given dtype and data
+    fn npy_of(descr: &str, len: usize, data: &[u8]) -> Vec<u8> {
+        let mut dict = format!("{{'descr': {descr}, 'fortran_order': False, 'shape': ({len},), }}");
+        while !(MAGIC.len() + 4 + dict.len() + 1).is_multiple_of(64) {
+            dict.push(' ');
+        }
+        dict.push('\n');
+        let mut file = MAGIC.to_vec();
+        file.extend([1, 0]);
+        file.extend((dict.len() as u16).to_le_bytes());
+        file.extend(dict.as_bytes());
+        file.extend(data);
+        file
+    }
+
+    /// Build a version 1.0 `.npy` file of little-endian f64 values
+    fn npy(values: &[f64]) -> Vec<u8> {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        npy_of("'<f8'", values.len(), &data)
+    }
+
+    #[test]
+    fn parse_header() {
+        let file = npy(&[1.0, 2.5, -3.0]);
+        let header = NpyHeader::parse(&file).unwrap().unwrap();
+        assert_eq!(header.descr, "<f8");
+        assert!(!header.fortran_order);
+        assert_eq!(header.shape, vec![3]);
+        assert_eq!(header.element_size, 8);
+        assert_eq!(header.len, 128);
+        assert_eq!(NpyHeader::parse(&file[..20]).unwrap(), None);
+        assert!(NpyHeader::parse(b"PK\x03\x04").is_err());
+    }
+
+    #[test]
+    fn roundtrip() {
+        let file = npy(&[1.0, 2.5, -3.0, f64::MAX, 0.0]);
+        for chunk_size in [1, 7, 4096] {
+            let mut sizes = Vec::new();
+            let mut encoder = Npy::new(|h: &NpyHeader| {
+                sizes.push(h.element_size);
+                ShrinkOrBypass::<Duplicate>::default()
+            });
+            let encoded = process_chunked(&mut encoder, &file, chunk_size);
+            assert_eq!(sizes, vec![8]);
+            assert_eq!(encoded[..128], file[..128]);
+            let mut decoder =
+                Npy::new(|_: &NpyHeader| ShrinkOrBypassDecoder::<Duplicate>::default());
+            assert_eq!(process_chunked(&mut decoder, &encoded, chunk_size), file);
+        }
+    }
+
+    #[test]
+    fn element_sizes() {
+        let size = |descr| {
+            let file = npy_of(descr, 1, &[]);
+            NpyHeader::parse(&file).map(|h| h.unwrap().element_size)
+        };
+        assert_eq!(size("'|u1'").unwrap(), 1);
+        assert_eq!(size("'>c16'").unwrap(), 16);
+        assert_eq!(size("'<U10'").unwrap(), 40);
+        assert_eq!(size("'|S10'").unwrap(), 10);
+        assert_eq!(size("'<M8[ns]'").unwrap(), 8);
+        assert_eq!(size("'<m8[s]'").unwrap(), 8);
+        assert_eq!(size("[('x', '<f8'), ('y', '<i4', (3,))]").unwrap(), 20);
+        assert_eq!(size("[('t', '<M8[us]'), ('id', '<U4')]").unwrap(), 24);
+        for unsupported in ["'|O'", "'<x8'", "[('a', [('b', '<f8')])]"] {
+            let err = size(unsupported).unwrap_err();
+            assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+        }
+    }
+
+    #[test]
+    fn float_pipeline() {
+        let values: Vec<f64> = (0..1000).map(|i| (i as f64 / 40.0).cos()).collect();
+        let file = npy(&values);
+        let header_len = NpyHeader::parse(&file).unwrap().unwrap().len;
+        for chunk_size in [1, 7, 4096] {
+            let encoded = process_chunked(&mut Npy::float_encoder(), &file, chunk_size);
+            assert_eq!(encoded[..header_len], file[..header_len]);
+            assert_ne!(encoded[header_len..], file[header_len..]);
+            let decoded = process_chunked(&mut Npy::float_decoder(), &encoded, chunk_size);
+            assert_eq!(decoded, file);
+        }
+        // Data of other dtypes is passed through
+        let file = npy_of("'<i2'", 3, &[1, 0, 2, 0, 3, 0]);
+        assert_eq!(process_chunked(&mut Npy::float_encoder(), &file, 5), file);
+    }
+
+    #[test]
+    fn truncated_header() {
+        let file = npy(&[1.0]);
+        let mut processor = Npy::new(|_: &NpyHeader| Duplicate::new());
+        processor.process(&file[..30], &mut Vec::new()).unwrap();
+        let err = processor.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+    }
+
+    #[test]
+    fn flush() {
+        let file = npy(&[1.0, 2.0]);
+        let header_len = file.len() - 16;
+        let mut sink = Vec::new();
+        let mut processor = Npy::new(|_: &NpyHeader| HoldBack::default());
+        processor
+            .process(&file[..header_len - 1], &mut sink)
+            .unwrap();
+        processor.flush(&mut sink).unwrap();
+        assert!(sink.is_empty());
+        processor
+            .process(&file[header_len - 1..], &mut sink)
+            .unwrap();
+        assert_eq!(sink, file[..header_len]);
+        processor.flush(&mut sink).unwrap();
+        assert_eq!(sink, file);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(
+            Npy::new(|_: &NpyHeader| Duplicate::new()),
+            &npy(&[1.0, 2.0]),
+        );
+    }
+}
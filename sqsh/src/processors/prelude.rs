@@ -0,0 +1,35 @@
+//! # Prelude
+//!
+//! Convenience module re-exporting all processors together with the core
+//! traits needed to drive them.
+//!
+//! ```
+//! use sqsh::processors::prelude::*;
+//!
+//! let mut sink = Vec::<u8>::new();
+//! let mut duplicate = Duplicate::new();
+//! duplicate.process("sqsh".as_bytes(), &mut sink).unwrap();
+//! assert_eq!(sink, "sqsh".as_bytes());
+//!
+//! let mut crc = CRC32::new();
+//! crc.process("sqsh".as_bytes(), &mut sink).unwrap();
+//! assert_eq!(crc.checksum(), 0x4a861156);
+//! ```
+#[cfg(feature = "std")]
+pub use crate::core::ProbTable;
+pub use crate::core::{
+    Checksum, ChecksumOutput, Decoder, Encoder, Invertible, Pipeline, Process, Reset, Symbol,
+};
+pub use crate::processors::{
+    Adler32, BatchCompressor, BitPack, BitUnpack, BwtDecoder, BwtEncoder, ByteShuffle,
+    ByteUnshuffle, CheckedChecksum, Crc16, Crc16Variant, Crc64, Crc64Variant, DeltaDecoder,
+    DeltaEncoder, Duplicate, EliasGammaDecoder, EliasGammaEncoder, Fletcher16, Fletcher32,
+    GorillaF64Decoder, GorillaF64Encoder, MatrixTransposeDecoder, MatrixTransposeEncoder,
+    MoveToFrontDecoder, MoveToFrontEncoder, Npy, RefDeltaDecoder, RefDeltaEncoder, RemapDecoder,
+    RemapEncoder, RiceDecoder, RiceEncoder, ShrinkOrBypass, ShrinkOrBypassDecoder,
+    SymbolDeltaDecoder, SymbolDeltaEncoder, ZigZagDecoder, ZigZagEncoder, CRC32,
+};
+#[cfg(feature = "std")]
+pub use crate::processors::{
+    ArithmeticDecoder, ArithmeticEncoder, HuffmanDecoder, HuffmanEncoder, LzwDecoder, LzwEncoder,
+};
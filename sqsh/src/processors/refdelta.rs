@@ -0,0 +1,216 @@
+//! # Reference Delta
+//!
+//! Delta encoding against a fixed reference instead of the previous sample.
+//! Each element of the source is reduced by the element at the same position
+//! of the reference. If the reference is shorter than the source it is
+//! repeated, so a single reference frame can be used for a whole series of
+//! frames.
+//!
+//! Elements are `element_size` bytes wide and interpreted as little-endian
+//! unsigned integers. Subtraction and addition wrap around, so the encoding
+//! is lossless for any input. A trailing partial element is treated as an
+//! element of reduced width.
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::Result;
+use alloc::vec::Vec;
+use log::info;
+
+/// Shared state of the reference delta encoder and decoder
+struct Reference {
+    reference: Vec<u8>,
+    element_size: usize,
+    position: usize,
+    carry: bool,
+}
+
+impl Reference {
+    fn new(reference: &[u8], element_size: usize) -> Self {
+        assert!(element_size > 0, "Element size must be positive");
+        assert!(
+            !reference.is_empty() && reference.len().is_multiple_of(element_size),
+            "Reference must consist of complete elements"
+        );
+        Reference {
+            reference: reference.to_vec(),
+            element_size,
+            position: 0,
+            carry: false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.position = 0;
+        self.carry = false;
+    }
+
+    /// Apply `op` on every byte of the source and its reference element
+    ///
+    /// The carry of the little-endian arithmetic is propagated within each
+    /// element, elements may be split across calls.
+    fn apply(&mut self, source: &[u8], sink: &mut Vec<u8>, op: fn(u8, u8, bool) -> (u8, bool)) {
+        for byte in source.iter() {
+            if self.position.is_multiple_of(self.element_size) {
+                self.carry = false;
+            }
+            let (result, carry) = op(*byte, self.reference[self.position], self.carry);
+            self.carry = carry;
+            sink.push(result);
+            self.position = (self.position + 1) % self.reference.len();
+        }
+    }
+}
+
+/// Subtract a little-endian byte with borrow
+fn sub(a: u8, b: u8, borrow: bool) -> (u8, bool) {
+    let (r, b1) = a.overflowing_sub(b);
+    let (r, b2) = r.overflowing_sub(borrow as u8);
+    (r, b1 || b2)
+}
+
+/// Add a little-endian byte with carry
+fn add(a: u8, b: u8, carry: bool) -> (u8, bool) {
+    let (r, c1) = a.overflowing_add(b);
+    let (r, c2) = r.overflowing_add(carry as u8);
+    (r, c1 || c2)
+}
+
+/// Encoder subtracting a reference from the source
+pub struct RefDeltaEncoder {
+    state: Reference,
+}
+
+impl RefDeltaEncoder {
+    /// Create a new encoder subtracting `reference` element-wise
+    ///
+    /// Panics if `element_size` is zero or the reference is empty or not a
+    /// multiple of `element_size`.
+    pub fn with_reference(reference: &[u8], element_size: usize) -> Self {
+        info!("New reference delta encoder with element size {element_size}");
+        RefDeltaEncoder {
+            state: Reference::new(reference, element_size),
+        }
+    }
+}
+
+impl Process for RefDeltaEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.apply(source, sink, sub);
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        self.reset();
+        Ok(0)
+    }
+}
+
+impl Reset for RefDeltaEncoder {
+    fn reset(&mut self) {
+        self.state.reset();
+    }
+}
+
+impl Encoder for RefDeltaEncoder {}
+
+/// Decoder adding the reference back onto the residuals
+pub struct RefDeltaDecoder {
+    state: Reference,
+}
+
+impl RefDeltaDecoder {
+    /// Create a new decoder adding `reference` element-wise
+    ///
+    /// Panics if `element_size` is zero or the reference is empty or not a
+    /// multiple of `element_size`.
+    pub fn with_reference(reference: &[u8], element_size: usize) -> Self {
+        info!("New reference delta decoder with element size {element_size}");
+        RefDeltaDecoder {
+            state: Reference::new(reference, element_size),
+        }
+    }
+}
+
+impl Process for RefDeltaDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.apply(source, sink, add);
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        self.reset();
+        Ok(0)
+    }
+}
+
+impl Reset for RefDeltaDecoder {
+    fn reset(&mut self) {
+        self.state.reset();
+    }
+}
+
+impl Decoder for RefDeltaDecoder {
+    type Encoder = RefDeltaEncoder;
+}
+
+/// Same decoder configuration as the encoder
+impl From<&RefDeltaEncoder> for RefDeltaDecoder {
+    fn from(encoder: &RefDeltaEncoder) -> Self {
+        Self::with_reference(&encoder.state.reference, encoder.state.element_size)
+    }
+}
+
+impl Invertible for RefDeltaEncoder {
+    type Decoder = RefDeltaDecoder;
+
+    fn inverse(&self) -> RefDeltaDecoder {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::{
+        multi_chunk_source, test_deterministic_with, test_finish_twice, test_roundtrip,
+    };
+
+    #[test]
+    fn equal_reference() {
+        let frame: Vec<u8> = (0..=255).rev().collect();
+        let encoded = test_roundtrip(RefDeltaEncoder::with_reference(&frame, 2), &frame.repeat(3));
+        assert!(encoded.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn different_reference() {
+        let encoder = RefDeltaEncoder::with_reference(&[1, 0, 0, 1], 2);
+        let encoded = test_roundtrip(encoder, &[0, 1, 5, 5, 2, 0, 0, 0]);
+        // 0x0100 - 0x0001 = 0x00FF, 0x0505 - 0x0100 = 0x0405
+        // 0x0002 - 0x0001 = 0x0001, 0x0000 - 0x0100 = 0xFF00
+        assert_eq!(encoded, [0xFF, 0x00, 0x05, 0x04, 0x01, 0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn partial_element() {
+        let encoder = RefDeltaEncoder::with_reference(&[7, 200, 3, 9], 4);
+        test_roundtrip(encoder, &[1, 2, 3, 4, 5, 6]);
+        test_roundtrip(RefDeltaEncoder::with_reference(&[255], 1), b"Wikipedia");
+    }
+
+    #[test]
+    #[should_panic]
+    fn incomplete_reference() {
+        RefDeltaEncoder::with_reference(&[1, 2, 3], 2);
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        let reference: Vec<u8> = (0..=255).collect();
+        test_deterministic_with(|| RefDeltaEncoder::with_reference(&reference, 4), &source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(RefDeltaEncoder::with_reference(&[1, 2], 2), &[5, 6, 7]);
+        test_finish_twice(RefDeltaDecoder::with_reference(&[1, 2], 2), &[5, 6, 7]);
+    }
+}
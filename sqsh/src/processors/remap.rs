@@ -0,0 +1,201 @@
+//! # Remap
+//!
+//! Remapping of the byte values by their frequency. Quantized scientific data
+//! often uses only a small subset of the 256 byte values. The remapping
+//! assigns the code `0` to the most frequent value, `1` to the second most
+//! frequent value, and so on, so the used values form a contiguous low range.
+//!
+//! The encoder works in block mode: the whole source is buffered and written
+//! in `finish`. The output starts with a header consisting of the number of
+//! used values minus one, followed by the used values ordered by descending
+//! frequency (ties are broken by the smaller value). The remapped data
+//! follows the header. An empty source produces an empty output.
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use alloc::format;
+use alloc::vec::Vec;
+use log::{debug, info};
+
+/// Encoder remapping byte values by their frequency
+pub struct RemapEncoder {
+    data: Vec<u8>,
+}
+
+impl RemapEncoder {
+    /// Create a new remap encoder
+    pub fn new() -> Self {
+        info!("New remap encoder");
+        RemapEncoder { data: Vec::new() }
+    }
+
+    /// Used byte values ordered by descending frequency
+    fn table(&self) -> Vec<u8> {
+        let mut counts = [0usize; 256];
+        self.data.iter().for_each(|b| counts[*b as usize] += 1);
+        let mut table: Vec<u8> = (0..=u8::MAX).filter(|b| counts[*b as usize] > 0).collect();
+        table.sort_by_key(|b| core::cmp::Reverse(counts[*b as usize]));
+        table
+    }
+}
+
+impl Default for RemapEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for RemapEncoder {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> Result<usize> {
+        self.data.extend(source);
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if self.data.is_empty() {
+            return Ok(0);
+        }
+        let table = self.table();
+        debug!("Remap table: {table:?}");
+        let mut codes = [0u8; 256];
+        for (code, value) in table.iter().enumerate() {
+            codes[*value as usize] = code as u8;
+        }
+        sink.push((table.len() - 1) as u8);
+        sink.extend(&table);
+        sink.extend(self.data.iter().map(|b| codes[*b as usize]));
+        self.data.clear();
+        Ok(0)
+    }
+}
+
+impl Reset for RemapEncoder {
+    fn reset(&mut self) {
+        self.data.clear();
+    }
+}
+
+impl Encoder for RemapEncoder {}
+
+impl Invertible for RemapEncoder {
+    type Decoder = RemapDecoder;
+
+    fn inverse(&self) -> RemapDecoder {
+        RemapDecoder::new()
+    }
+}
+
+/// Decoder restoring the original byte values of the `RemapEncoder`
+pub struct RemapDecoder {
+    symbols: Option<usize>,
+    table: Vec<u8>,
+}
+
+impl RemapDecoder {
+    /// Create a new remap decoder
+    pub fn new() -> Self {
+        info!("New remap decoder");
+        RemapDecoder {
+            symbols: None,
+            table: Vec::with_capacity(256),
+        }
+    }
+}
+
+impl Default for RemapDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for RemapDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            match self.symbols {
+                None => self.symbols = Some(*byte as usize + 1),
+                Some(n) if self.table.len() < n => self.table.push(*byte),
+                Some(_) => match self.table.get(*byte as usize) {
+                    Some(value) => sink.push(*value),
+                    None => {
+                        let msg = format!("Invalid remap code: {byte}");
+                        return Err(Error::invalid_code(msg));
+                    }
+                },
+            }
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        let incomplete = matches!(self.symbols, Some(n) if self.table.len() < n);
+        self.reset();
+        if incomplete {
+            return Err(Error::truncated("Incomplete remap header"));
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for RemapDecoder {
+    fn reset(&mut self) {
+        self.symbols = None;
+        self.table.clear();
+    }
+}
+
+impl Decoder for RemapDecoder {
+    type Encoder = RemapEncoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::{
+        multi_chunk_source, test_deterministic_process, test_finish_twice, test_roundtrip,
+    };
+
+    #[test]
+    fn sparse_alphabet() {
+        let source = [200, 40, 200, 200, 120, 40, 200];
+        let encoded = test_roundtrip(RemapEncoder::new(), &source);
+        assert_eq!(encoded[..4], [2, 200, 40, 120]);
+        assert_eq!(encoded[4..], [0, 1, 0, 0, 2, 1, 0]);
+    }
+
+    #[test]
+    fn quantized() {
+        let source: Vec<u8> = (0..1000u32).map(|i| ((i * 7 % 13) * 16) as u8).collect();
+        let encoded = test_roundtrip(RemapEncoder::new(), &source);
+        assert_eq!(encoded[0], 12);
+        assert!(encoded[14..].iter().all(|code| *code < 13));
+    }
+
+    #[test]
+    fn full_alphabet() {
+        let source: Vec<u8> = (0..=255).rev().collect();
+        let encoded = test_roundtrip(RemapEncoder::new(), &source);
+        assert_eq!(encoded[0], 255);
+        assert_eq!(encoded[1..257], (0..=255).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn empty() {
+        assert!(test_roundtrip(RemapEncoder::new(), &[]).is_empty());
+    }
+
+    #[test]
+    fn invalid_code() {
+        let mut decoder = RemapDecoder::new();
+        let err = decoder.process(&[1, 7, 9, 2], &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<RemapEncoder>(&source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(RemapEncoder::new(), "Wikipedia".as_bytes());
+        test_finish_twice(RemapDecoder::new(), &[1, 7, 9, 0, 1, 1]);
+    }
+}
@@ -0,0 +1,367 @@
+//! # Golomb-Rice
+//!
+//! Rice coding as described
+//! [here](https://en.wikipedia.org/wiki/Golomb_coding#Rice_coding), suited
+//! for geometrically distributed values like the zig-zag mapped residuals of
+//! a `DeltaEncoder`.
+//!
+//! Every byte `b` is split into the quotient `b >> k` and the lowest `k`
+//! bits. The quotient is written in unary as zeros terminated by a one bit,
+//! followed by the remainder, MSB-first.
+//!
+//! In adaptive mode the source is coded in blocks of 256 bytes, the last
+//! block may be shorter. Every block starts with its `k` in 3 bits, chosen
+//! as the integer part of the binary logarithm of the mean byte of the
+//! block (`0` for a mean below 2).
+//!
+//! The last code is padded with zeros to a complete byte. As every code
+//! contains a one bit, the padding is never mistaken for a code.
+use crate::core::bits::{BitReader, BitWriter};
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use alloc::vec::Vec;
+use log::{debug, info};
+
+/// Number of bytes per block in adaptive mode
+const BLOCK_SIZE: usize = 256;
+/// Bits of the `k` of an adaptive block
+const K_BITS: u32 = 3;
+
+fn check_k(k: usize) -> u32 {
+    assert!(k < 8, "Rice parameter must be below 8");
+    k as u32
+}
+
+/// Parameter `k` for a block of bytes
+fn block_k(block: &[u8]) -> u32 {
+    let sum: usize = block.iter().map(|b| *b as usize).sum();
+    let mean = sum / block.len();
+    (usize::BITS - mean.leading_zeros()).saturating_sub(1)
+}
+
+/// Rice encoder with a fixed or adaptive parameter `k`
+pub struct RiceEncoder {
+    /// Fixed parameter, `None` in adaptive mode
+    k: Option<u32>,
+    writer: BitWriter,
+    block: Vec<u8>,
+}
+
+impl RiceEncoder {
+    /// Create a new Rice encoder in adaptive mode
+    pub fn new() -> Self {
+        info!("New adaptive Rice encoder");
+        RiceEncoder {
+            k: None,
+            writer: BitWriter::new(),
+            block: Vec::with_capacity(BLOCK_SIZE),
+        }
+    }
+
+    /// Create a new Rice encoder with a fixed parameter `k`
+    ///
+    /// Panics if `k` is not below 8.
+    pub fn with_k(k: usize) -> Self {
+        let k = check_k(k);
+        info!("New Rice encoder with k {k}");
+        RiceEncoder {
+            k: Some(k),
+            writer: BitWriter::new(),
+            block: Vec::new(),
+        }
+    }
+
+    fn write(&mut self, byte: u8, k: u32, sink: &mut Vec<u8>) {
+        let mut quotient = (byte >> k) as u32;
+        while quotient > 0 {
+            let zeros = quotient.min(56);
+            self.writer.write(0, zeros, sink);
+            quotient -= zeros;
+        }
+        self.writer.write(1, 1, sink);
+        self.writer.write(byte as u64, k, sink);
+    }
+
+    fn write_block(&mut self, sink: &mut Vec<u8>) {
+        let block = core::mem::take(&mut self.block);
+        let k = block_k(&block);
+        debug!("Rice block of {} bytes with k {k}", block.len());
+        self.writer.write(k as u64, K_BITS, sink);
+        for byte in block.iter() {
+            self.write(*byte, k, sink);
+        }
+        self.block = block;
+        self.block.clear();
+    }
+}
+
+impl Default for RiceEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for RiceEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            match self.k {
+                Some(k) => self.write(*byte, k, sink),
+                None => {
+                    self.block.push(*byte);
+                    if self.block.len() == BLOCK_SIZE {
+                        self.write_block(sink);
+                    }
+                }
+            }
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if !self.block.is_empty() {
+            self.write_block(sink);
+        }
+        self.writer.finish(sink);
+        Ok(0)
+    }
+}
+
+impl Reset for RiceEncoder {
+    fn reset(&mut self) {
+        self.writer.reset();
+        self.block.clear();
+    }
+}
+
+impl Encoder for RiceEncoder {}
+
+impl Invertible for RiceEncoder {
+    type Decoder = RiceDecoder;
+
+    fn inverse(&self) -> RiceDecoder {
+        match self.k {
+            Some(k) => RiceDecoder::with_k(k as usize),
+            None => RiceDecoder::new(),
+        }
+    }
+}
+
+/// Part of a code the decoder expects next
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    BlockK,
+    Quotient,
+    Remainder,
+}
+
+/// Rice decoder restoring the bytes of the `RiceEncoder`
+pub struct RiceDecoder {
+    adaptive: bool,
+    k: u32,
+    reader: BitReader,
+    stage: Stage,
+    quotient: u32,
+    /// Number of bytes decoded in the current adaptive block
+    decoded: usize,
+    /// Bits read since the last decoded byte
+    unfinished: u32,
+    /// Whether all bits read since the last decoded byte are zero
+    zeros: bool,
+}
+
+impl RiceDecoder {
+    /// Create a new Rice decoder in adaptive mode
+    pub fn new() -> Self {
+        info!("New adaptive Rice decoder");
+        Self::with_mode(true, 0)
+    }
+
+    /// Create a new Rice decoder with a fixed parameter `k`
+    ///
+    /// Panics if `k` is not below 8.
+    pub fn with_k(k: usize) -> Self {
+        let k = check_k(k);
+        info!("New Rice decoder with k {k}");
+        Self::with_mode(false, k)
+    }
+
+    fn with_mode(adaptive: bool, k: u32) -> Self {
+        let mut decoder = RiceDecoder {
+            adaptive,
+            k,
+            reader: BitReader::new(),
+            stage: Stage::Quotient,
+            quotient: 0,
+            decoded: 0,
+            unfinished: 0,
+            zeros: true,
+        };
+        decoder.reset();
+        decoder
+    }
+
+    /// Read bits for the current stage, `None` if more data is needed
+    fn read(&mut self, bits: u32) -> Option<u64> {
+        let value = self.reader.read(bits)?;
+        self.unfinished += bits;
+        self.zeros &= value == 0;
+        Some(value)
+    }
+
+    /// Decode all complete codes of the pending bits
+    fn decode(&mut self, sink: &mut Vec<u8>) -> Result<()> {
+        loop {
+            match self.stage {
+                Stage::BlockK => {
+                    let Some(k) = self.read(K_BITS) else {
+                        return Ok(());
+                    };
+                    self.k = k as u32;
+                    self.stage = Stage::Quotient;
+                }
+                Stage::Quotient => {
+                    let Some(bit) = self.read(1) else {
+                        return Ok(());
+                    };
+                    self.quotient += (bit == 0) as u32;
+                    // Less than a byte of zeros may still be the padding
+                    let complete = bit == 1 || self.unfinished >= 8;
+                    if complete && self.quotient << self.k > u8::MAX as u32 {
+                        return Err(Error::invalid_code("Rice code exceeds a byte"));
+                    }
+                    if bit == 1 {
+                        self.stage = Stage::Remainder;
+                    }
+                }
+                Stage::Remainder => {
+                    let Some(remainder) = self.read(self.k) else {
+                        return Ok(());
+                    };
+                    sink.push(((self.quotient << self.k) as u64 | remainder) as u8);
+                    self.quotient = 0;
+                    self.unfinished = 0;
+                    self.zeros = true;
+                    self.decoded += 1;
+                    self.stage = Stage::Quotient;
+                    if self.adaptive && self.decoded == BLOCK_SIZE {
+                        self.decoded = 0;
+                        self.stage = Stage::BlockK;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for RiceDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for RiceDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        for byte in source.iter() {
+            self.reader.push(*byte);
+            self.decode(sink)?;
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        // Less than a byte of zeros is the padding of the last byte
+        let padding = self.zeros
+            && self.reader.remainder() == 0
+            && self.unfinished + self.reader.pending() < 8;
+        self.reset();
+        if !padding {
+            return Err(Error::truncated("Truncated Rice code"));
+        }
+        Ok(0)
+    }
+}
+
+impl Reset for RiceDecoder {
+    fn reset(&mut self) {
+        self.reader.reset();
+        self.stage = if self.adaptive {
+            Stage::BlockK
+        } else {
+            Stage::Quotient
+        };
+        self.quotient = 0;
+        self.decoded = 0;
+        self.unfinished = 0;
+        self.zeros = true;
+    }
+}
+
+impl Decoder for RiceDecoder {
+    type Encoder = RiceEncoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+    use crate::processors::{DeltaEncoder, ZigZagEncoder};
+
+    /// Zig-zag mapped deltas of a slowly varying signal
+    fn residuals(len: usize) -> Vec<u8> {
+        let signal: Vec<u8> = (0..len)
+            .map(|i| (128.0 + 100.0 * (i as f64 / 50.0).sin()) as u8)
+            .collect();
+        let deltas = process_chunked(&mut DeltaEncoder::new(), &signal, 4096);
+        process_chunked(&mut ZigZagEncoder::new(), &deltas, 4096)
+    }
+
+    #[test]
+    fn fixed_k() {
+        let source: Vec<u8> = (0..=u8::MAX).collect();
+        for k in 0..8 {
+            test_roundtrip(RiceEncoder::with_k(k), &source);
+        }
+        // 5 -> 01 01, 0 -> 1 00, 3 -> 1 11
+        let encoded = test_roundtrip(RiceEncoder::with_k(2), &[5, 0, 3]);
+        assert_eq!(encoded, [0b0101_1001, 0b1100_0000]);
+    }
+
+    #[test]
+    fn adaptive() {
+        let source = residuals(5000);
+        let encoded = test_roundtrip(RiceEncoder::new(), &source);
+        assert!(encoded.len() < source.len() / 2);
+        let mut mixed = vec![0; 300];
+        mixed.extend((0..=u8::MAX).cycle().take(700));
+        test_roundtrip(RiceEncoder::new(), &mixed);
+        assert_eq!(block_k(&[0, 1, 2]), 0);
+        assert_eq!(block_k(&[255; 4]), 7);
+        assert!(test_roundtrip(RiceEncoder::new(), &[]).is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        let mut decoder = RiceDecoder::with_k(7);
+        let err = decoder
+            .process(&[0b0011_1111], &mut Vec::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+
+        let mut decoder = RiceDecoder::with_k(4);
+        decoder.process(&[0b0000_0010], &mut Vec::new()).unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<RiceEncoder>(&source);
+        test_deterministic_with(|| RiceEncoder::with_k(3), &source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(RiceEncoder::new(), &[1, 2, 3]);
+        test_finish_twice(RiceEncoder::with_k(3), &[1, 2, 3]);
+        test_finish_twice(RiceDecoder::with_k(2), &[0b0101_1001, 0b1100_0000]);
+    }
+}
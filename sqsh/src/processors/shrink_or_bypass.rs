@@ -0,0 +1,315 @@
+//! # Shrink or Bypass
+//!
+//! Wrapper around any encoding processor guaranteeing that the output never
+//! exceeds the input by more than a single byte. The inner processor output
+//! is only used if it is smaller than the input, otherwise the input is
+//! stored verbatim.
+//!
+//! The output is prefixed by a flag byte: `0` marks data produced by the
+//! inner processor, `1` marks stored data. Both input and output are kept in
+//! memory until `finish` is called. The encoder therefore cannot flush, the
+//! decoder flushes its inner decoder.
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use alloc::format;
+use alloc::vec::Vec;
+use log::{debug, info};
+
+/// Flag marking output produced by the inner processor
+const ENCODED: u8 = 0;
+/// Flag marking verbatim stored input
+const STORED: u8 = 1;
+
+/// Run the inner encoder and store the input if the encoder expanded it
+pub struct ShrinkOrBypass<P> {
+    inner: P,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    finished: bool,
+}
+
+impl<P: Process> ShrinkOrBypass<P> {
+    /// Wrap the inner encoder
+    pub fn new(inner: P) -> Self {
+        ShrinkOrBypass {
+            inner,
+            input: Vec::new(),
+            output: Vec::new(),
+            finished: false,
+        }
+    }
+}
+
+impl<P: Process + Default> Default for ShrinkOrBypass<P> {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<P: Process> Process for ShrinkOrBypass<P> {
+    fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> Result<usize> {
+        self.inner.process(source, &mut self.output)?;
+        self.input.extend(source);
+        self.finished = false;
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if self.finished {
+            return Ok(0);
+        }
+        self.finished = true;
+        self.inner.finish(&mut self.output)?;
+        if self.output.len() < self.input.len() {
+            debug!("Inner processor shrunk the data, using its output");
+            sink.push(ENCODED);
+            sink.append(&mut self.output);
+        } else {
+            info!("Inner processor did not shrink the data, storing input");
+            sink.push(STORED);
+            sink.append(&mut self.input);
+        }
+        self.input.clear();
+        self.output.clear();
+        Ok(0)
+    }
+    /// Emits nothing: whether the input is stored is only known once the
+    /// whole input was seen, so all data is held back until `finish`
+    fn flush(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+impl<P: Reset> Reset for ShrinkOrBypass<P> {
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.input.clear();
+        self.output.clear();
+        self.finished = false;
+    }
+}
+
+impl<P: Encoder> Encoder for ShrinkOrBypass<P> {}
+
+impl<P: Invertible> Invertible for ShrinkOrBypass<P> {
+    type Decoder = ShrinkOrBypassDecoder<P::Decoder>;
+
+    fn inverse(&self) -> Self::Decoder {
+        ShrinkOrBypassDecoder::new(self.inner.inverse())
+    }
+}
+
+/// Decode the output of `ShrinkOrBypass` with the inner decoder
+pub struct ShrinkOrBypassDecoder<P> {
+    inner: P,
+    flag: Option<u8>,
+    finished: bool,
+}
+
+impl<P: Process> ShrinkOrBypassDecoder<P> {
+    /// Wrap the inner decoder
+    pub fn new(inner: P) -> Self {
+        ShrinkOrBypassDecoder {
+            inner,
+            flag: None,
+            finished: false,
+        }
+    }
+}
+
+impl<P: Process + Default> Default for ShrinkOrBypassDecoder<P> {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<P: Process> Process for ShrinkOrBypassDecoder<P> {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.finished = false;
+        let data = match self.flag {
+            Some(_) => source,
+            None => match source.split_first() {
+                Some((&flag @ (ENCODED | STORED), rest)) => {
+                    self.flag = Some(flag);
+                    rest
+                }
+                Some((flag, _)) => {
+                    let msg = format!("Unknown ShrinkOrBypass flag: {flag}");
+                    return Err(Error::invalid_code(msg));
+                }
+                None => return Ok(0),
+            },
+        };
+        match self.flag {
+            Some(STORED) => sink.extend(data),
+            _ => {
+                self.inner.process(data, sink)?;
+            }
+        }
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        if self.finished {
+            return Ok(0);
+        }
+        self.finished = true;
+        match self.flag.take() {
+            Some(ENCODED) => self.inner.finish(sink),
+            Some(_) => Ok(0),
+            None => Err(Error::truncated("Missing ShrinkOrBypass flag")),
+        }
+    }
+    fn flush(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        match self.flag {
+            Some(ENCODED) => self.inner.flush(sink),
+            _ => Ok(0),
+        }
+    }
+}
+
+impl<P: Decoder> Decoder for ShrinkOrBypassDecoder<P> {
+    type Encoder = ShrinkOrBypass<P::Encoder>;
+}
+
+impl<P: Reset> Reset for ShrinkOrBypassDecoder<P> {
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.flag = None;
+        self.finished = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::{test_finish_twice, HoldBack};
+
+    /// Naive run-length encoder writing `(count, byte)` pairs
+    #[derive(Default)]
+    struct PairEncoder {
+        data: Vec<u8>,
+    }
+
+    impl Process for PairEncoder {
+        fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> Result<usize> {
+            self.data.extend(source);
+            Ok(source.len())
+        }
+        fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+            for run in self.data.chunk_by(|a, b| a == b) {
+                for part in run.chunks(u8::MAX as usize) {
+                    sink.extend([part.len() as u8, part[0]]);
+                }
+            }
+            self.data.clear();
+            Ok(0)
+        }
+    }
+
+    /// Decoder for the `PairEncoder`
+    #[derive(Default)]
+    struct PairDecoder {
+        data: Vec<u8>,
+    }
+
+    impl Process for PairDecoder {
+        fn process(&mut self, source: &[u8], _: &mut Vec<u8>) -> Result<usize> {
+            self.data.extend(source);
+            Ok(source.len())
+        }
+        fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+            for pair in self.data.chunks(2) {
+                sink.extend(core::iter::repeat_n(pair[1], pair[0] as usize));
+            }
+            self.data.clear();
+            Ok(0)
+        }
+    }
+
+    fn encode(source: &[u8]) -> Vec<u8> {
+        let mut encoder = ShrinkOrBypass::<PairEncoder>::default();
+        let mut encoded = Vec::new();
+        encoder.process(source, &mut encoded).unwrap();
+        encoder.finish(&mut encoded).unwrap();
+        encoded
+    }
+
+    fn decode(source: &[u8], chunk_size: usize) -> Vec<u8> {
+        let mut decoder = ShrinkOrBypassDecoder::<PairDecoder>::default();
+        let mut decoded = Vec::new();
+        for chunk in source.chunks(chunk_size) {
+            decoder.process(chunk, &mut decoded).unwrap();
+        }
+        decoder.finish(&mut decoded).unwrap();
+        decoded
+    }
+
+    #[test]
+    fn compressible() {
+        let source = "aaaaaaaaaabbbbbbbbbbbbcccccccc".as_bytes();
+        let encoded = encode(source);
+        assert_eq!(encoded[0], ENCODED);
+        assert_eq!(encoded, [ENCODED, 10, b'a', 12, b'b', 8, b'c']);
+        assert_eq!(decode(&encoded, 1), source);
+        assert_eq!(decode(&encoded, 4096), source);
+    }
+
+    #[test]
+    fn incompressible() {
+        let source = "Wikipedia".as_bytes();
+        let encoded = encode(source);
+        assert_eq!(encoded[0], STORED);
+        assert_eq!(encoded.len(), source.len() + 1);
+        assert_eq!(decode(&encoded, 1), source);
+        assert_eq!(decode(&encoded, 4096), source);
+    }
+
+    #[test]
+    fn empty() {
+        let encoded = encode(&[]);
+        assert_eq!(encoded, [STORED]);
+        assert!(decode(&encoded, 1).is_empty());
+    }
+
+    #[test]
+    fn unknown_flag() {
+        let mut decoder = ShrinkOrBypassDecoder::<PairDecoder>::default();
+        let err = decoder.process(&[7, 1, 2], &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidCode(_)), "{err:?}");
+    }
+
+    #[test]
+    fn missing_flag() {
+        let mut decoder = ShrinkOrBypassDecoder::<PairDecoder>::default();
+        decoder.process(&[], &mut Vec::new()).unwrap();
+        let err = decoder.finish(&mut Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+    }
+
+    #[test]
+    fn flush() {
+        let mut sink = Vec::new();
+        let mut encoder = ShrinkOrBypass::<HoldBack>::default();
+        encoder.process(&[1, 2, 3], &mut sink).unwrap();
+        encoder.flush(&mut sink).unwrap();
+        assert!(sink.is_empty());
+
+        let mut decoder = ShrinkOrBypassDecoder::<HoldBack>::default();
+        decoder.process(&[ENCODED, 1, 2], &mut sink).unwrap();
+        assert!(sink.is_empty());
+        decoder.flush(&mut sink).unwrap();
+        assert_eq!(sink, [1, 2]);
+        decoder.process(&[3], &mut sink).unwrap();
+        decoder.finish(&mut sink).unwrap();
+        assert_eq!(sink, [1, 2, 3]);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(ShrinkOrBypass::<PairEncoder>::default(), &[1, 1, 1, 1]);
+        test_finish_twice(ShrinkOrBypass::<PairEncoder>::default(), &[]);
+        test_finish_twice(
+            ShrinkOrBypassDecoder::<PairDecoder>::default(),
+            &[STORED, 1, 2],
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! # Byte Shuffle
+//!
+//! Byte-stream splitting of arrays of fixed-size elements, usually `f32` or
+//! `f64` values. The bytes of the elements are regrouped into planes: first
+//! the first byte of every element, then the second byte of every element,
+//! and so on. Sign and exponent bytes of similar floating-point values end
+//! up next to each other, which helps the following encoders.
+//!
+//! The source is shuffled in blocks of `block_size` elements, the last block
+//! may be shorter. Trailing bytes not forming a complete element are passed
+//! through unchanged after the last block. The unshuffle of the last block
+//! relies on the same split, so it is done in `finish`.
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::Result;
+use alloc::vec::Vec;
+use log::info;
+
+/// Default number of elements per block
+const DEFAULT_BLOCK_SIZE: usize = 4096;
+
+/// Write `data` as a transposed `rows` x `cols` grid of bytes
+fn transpose(data: &[u8], rows: usize, cols: usize, sink: &mut Vec<u8>) {
+    for c in 0..cols {
+        sink.extend((0..rows).map(|r| data[r * cols + c]));
+    }
+}
+
+/// Shuffle or unshuffle of blocks of elements
+struct Shuffle {
+    element_size: usize,
+    block_size: usize,
+    unshuffle: bool,
+    buffer: Vec<u8>,
+}
+
+impl Shuffle {
+    fn new(element_size: usize, block_size: usize, unshuffle: bool) -> Self {
+        assert!(
+            element_size == 4 || element_size == 8,
+            "Element size must be 4 or 8 bytes"
+        );
+        assert!(block_size > 0, "Block size must be positive");
+        Shuffle {
+            element_size,
+            block_size,
+            unshuffle,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Write a block of complete elements
+    fn block(&self, block: &[u8], sink: &mut Vec<u8>) {
+        let elements = block.len() / self.element_size;
+        if self.unshuffle {
+            transpose(block, self.element_size, elements, sink);
+        } else {
+            transpose(block, elements, self.element_size, sink);
+        }
+    }
+
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) {
+        let block_bytes = self.block_size * self.element_size;
+        self.buffer.extend(source);
+        let mut blocks = self.buffer.chunks_exact(block_bytes);
+        for block in blocks.by_ref() {
+            self.block(block, sink);
+        }
+        let consumed = self.buffer.len() - blocks.remainder().len();
+        self.buffer.drain(..consumed);
+    }
+
+    fn finish(&mut self, sink: &mut Vec<u8>) {
+        let split = self.buffer.len() - self.buffer.len() % self.element_size;
+        self.block(&self.buffer[..split], sink);
+        sink.extend(&self.buffer[split..]);
+        self.buffer.clear();
+    }
+}
+
+/// Encoder splitting elements into byte planes
+pub struct ByteShuffle {
+    state: Shuffle,
+}
+
+impl ByteShuffle {
+    /// Create a new shuffle of elements of `element_size` bytes
+    ///
+    /// Panics if `element_size` is not 4 or 8.
+    pub fn new(element_size: usize) -> Self {
+        Self::with_block_size(element_size, DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Create a new shuffle of blocks of `block_size` elements
+    ///
+    /// Panics if `element_size` is not 4 or 8 or `block_size` is zero.
+    pub fn with_block_size(element_size: usize, block_size: usize) -> Self {
+        info!("New byte shuffle of {element_size} byte elements");
+        ByteShuffle {
+            state: Shuffle::new(element_size, block_size, false),
+        }
+    }
+}
+
+impl Process for ByteShuffle {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.process(source, sink);
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.finish(sink);
+        Ok(0)
+    }
+}
+
+impl Reset for ByteShuffle {
+    fn reset(&mut self) {
+        self.state.buffer.clear();
+    }
+}
+
+impl Encoder for ByteShuffle {}
+
+impl Invertible for ByteShuffle {
+    type Decoder = ByteUnshuffle;
+
+    fn inverse(&self) -> ByteUnshuffle {
+        ByteUnshuffle::with_block_size(self.state.element_size, self.state.block_size)
+    }
+}
+
+/// Decoder restoring the elements of the `ByteShuffle`
+pub struct ByteUnshuffle {
+    state: Shuffle,
+}
+
+impl ByteUnshuffle {
+    /// Create a new unshuffle of elements of `element_size` bytes
+    ///
+    /// Panics if `element_size` is not 4 or 8.
+    pub fn new(element_size: usize) -> Self {
+        Self::with_block_size(element_size, DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Create a new unshuffle of blocks of `block_size` elements
+    ///
+    /// Panics if `element_size` is not 4 or 8 or `block_size` is zero.
+    pub fn with_block_size(element_size: usize, block_size: usize) -> Self {
+        info!("New byte unshuffle of {element_size} byte elements");
+        ByteUnshuffle {
+            state: Shuffle::new(element_size, block_size, true),
+        }
+    }
+}
+
+impl Process for ByteUnshuffle {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.process(source, sink);
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.finish(sink);
+        Ok(0)
+    }
+}
+
+impl Reset for ByteUnshuffle {
+    fn reset(&mut self) {
+        self.state.buffer.clear();
+    }
+}
+
+impl Decoder for ByteUnshuffle {
+    type Encoder = ByteShuffle;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+
+    #[test]
+    fn planes() {
+        let source: Vec<u8> = (1..=3u32).flat_map(|v| v.to_be_bytes()).collect();
+        let encoded = test_roundtrip(ByteShuffle::new(4), &source);
+        assert_eq!(encoded, [0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn floats() {
+        let source: Vec<u8> = (0..10_000)
+            .flat_map(|i| (i as f64 * 0.25).to_le_bytes())
+            .collect();
+        test_roundtrip(ByteShuffle::new(8), &source);
+        test_roundtrip(ByteShuffle::with_block_size(8, 7), &source);
+        let source: Vec<u8> = (0..10_000)
+            .flat_map(|i| (i as f32).sqrt().to_le_bytes())
+            .collect();
+        test_roundtrip(ByteShuffle::with_block_size(4, 100), &source);
+    }
+
+    #[test]
+    fn remainder() {
+        let source: Vec<u8> = (0..=200).collect();
+        let encoded = test_roundtrip(ByteShuffle::with_block_size(8, 4), &source);
+        // 201 bytes are 6 blocks, a block of a single element and a byte
+        assert_eq!(encoded[160..168], [160, 168, 176, 184, 161, 169, 177, 185]);
+        assert!(encoded[192..].iter().copied().eq(192..=200));
+        test_roundtrip(ByteShuffle::new(4), &[1, 2, 3]);
+        assert!(test_roundtrip(ByteShuffle::new(4), &[]).is_empty());
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_with(|| ByteShuffle::new(8), &source);
+        test_deterministic_with(|| ByteShuffle::with_block_size(4, 100), &source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(ByteShuffle::new(4), &[1, 2, 3, 4, 5, 6]);
+        test_finish_twice(ByteUnshuffle::new(4), &[1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,219 @@
+//! # Matrix Transpose
+//!
+//! Transposition of 2D grids of fixed-size elements. Scientific grids are
+//! usually stored row-major, while neighbouring values of a column are often
+//! more correlated. Transposing the grid makes the columns contiguous, which
+//! helps the following processors.
+//!
+//! The source is a sequence of complete grids of `rows * cols` elements,
+//! each `element_size` bytes wide. Every grid is emitted as soon as it is
+//! complete. A trailing incomplete grid is not padded, `finish` returns a
+//! `Truncated` error instead.
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::{Error, Result};
+use alloc::format;
+use alloc::vec::Vec;
+use log::info;
+
+/// Transposition of a `rows` x `cols` grid
+struct Transpose {
+    rows: usize,
+    cols: usize,
+    element_size: usize,
+    buffer: Vec<u8>,
+}
+
+impl Transpose {
+    fn new(rows: usize, cols: usize, element_size: usize) -> Self {
+        assert!(
+            rows > 0 && cols > 0 && element_size > 0,
+            "Grid dimensions and element size must be positive"
+        );
+        Transpose {
+            rows,
+            cols,
+            element_size,
+            buffer: Vec::with_capacity(rows * cols * element_size),
+        }
+    }
+
+    fn grid_size(&self) -> usize {
+        self.rows * self.cols * self.element_size
+    }
+
+    /// Transpose a single complete grid into the sink
+    fn transpose(&self, grid: &[u8], sink: &mut Vec<u8>) {
+        let size = self.element_size;
+        for c in 0..self.cols {
+            for r in 0..self.rows {
+                let start = (r * self.cols + c) * size;
+                sink.extend(&grid[start..start + size]);
+            }
+        }
+    }
+
+    fn process(&mut self, mut source: &[u8], sink: &mut Vec<u8>) {
+        let grid_size = self.grid_size();
+        if !self.buffer.is_empty() {
+            let missing = (grid_size - self.buffer.len()).min(source.len());
+            self.buffer.extend(&source[..missing]);
+            source = &source[missing..];
+            if self.buffer.len() < grid_size {
+                return;
+            }
+            let grid = core::mem::take(&mut self.buffer);
+            self.transpose(&grid, sink);
+            self.buffer = grid;
+            self.buffer.clear();
+        }
+        let mut grids = source.chunks_exact(grid_size);
+        for grid in grids.by_ref() {
+            self.transpose(grid, sink);
+        }
+        self.buffer.extend(grids.remainder());
+    }
+
+    fn finish(&mut self) -> Result<usize> {
+        if self.buffer.is_empty() {
+            return Ok(0);
+        }
+        let msg = format!(
+            "Incomplete grid: {} of {} bytes",
+            self.buffer.len(),
+            self.grid_size()
+        );
+        self.buffer.clear();
+        Err(Error::truncated(msg))
+    }
+}
+
+/// Encoder transposing row-major grids into column-major grids
+pub struct MatrixTransposeEncoder {
+    state: Transpose,
+}
+
+impl MatrixTransposeEncoder {
+    /// Create a new encoder for grids of `rows` x `cols` elements
+    ///
+    /// Panics if any of the arguments is zero.
+    pub fn new(rows: usize, cols: usize, element_size: usize) -> Self {
+        info!("New matrix transpose encoder for {rows}x{cols} grids");
+        MatrixTransposeEncoder {
+            state: Transpose::new(rows, cols, element_size),
+        }
+    }
+}
+
+impl Process for MatrixTransposeEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.process(source, sink);
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        self.state.finish()
+    }
+}
+
+impl Reset for MatrixTransposeEncoder {
+    fn reset(&mut self) {
+        self.state.buffer.clear();
+    }
+}
+
+impl Encoder for MatrixTransposeEncoder {}
+
+impl Invertible for MatrixTransposeEncoder {
+    type Decoder = MatrixTransposeDecoder;
+
+    fn inverse(&self) -> MatrixTransposeDecoder {
+        let s = &self.state;
+        MatrixTransposeDecoder::new(s.rows, s.cols, s.element_size)
+    }
+}
+
+/// Decoder restoring the row-major grids of the `MatrixTransposeEncoder`
+pub struct MatrixTransposeDecoder {
+    state: Transpose,
+}
+
+impl MatrixTransposeDecoder {
+    /// Create a new decoder for grids originally of `rows` x `cols` elements
+    ///
+    /// Panics if any of the arguments is zero.
+    pub fn new(rows: usize, cols: usize, element_size: usize) -> Self {
+        info!("New matrix transpose decoder for {rows}x{cols} grids");
+        MatrixTransposeDecoder {
+            state: Transpose::new(cols, rows, element_size),
+        }
+    }
+}
+
+impl Process for MatrixTransposeDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.process(source, sink);
+        Ok(source.len())
+    }
+    fn finish(&mut self, _: &mut Vec<u8>) -> Result<usize> {
+        self.state.finish()
+    }
+}
+
+impl Reset for MatrixTransposeDecoder {
+    fn reset(&mut self) {
+        self.state.buffer.clear();
+    }
+}
+
+impl Decoder for MatrixTransposeDecoder {
+    type Encoder = MatrixTransposeEncoder;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::{
+        multi_chunk_source, test_deterministic_with, test_finish_twice, test_roundtrip,
+    };
+
+    #[test]
+    fn element_positions() {
+        // 2x3 grid of u16: [[1, 2, 3], [4, 5, 6]]
+        let grid: Vec<u8> = (1..=6u16).flat_map(|v| v.to_le_bytes()).collect();
+        let expected: Vec<u8> = [1, 4, 2, 5, 3, 6u16]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let transposed = test_roundtrip(MatrixTransposeEncoder::new(2, 3, 2), &grid);
+        assert_eq!(transposed, expected);
+    }
+
+    #[test]
+    fn multiple_grids() {
+        let source: Vec<u8> = (0..=255).collect();
+        let transposed = test_roundtrip(MatrixTransposeEncoder::new(4, 8, 1), &source);
+        assert_eq!(transposed[..4], [0, 8, 16, 24]);
+    }
+
+    #[test]
+    fn incomplete_grid() {
+        let mut encoder = MatrixTransposeEncoder::new(2, 2, 4);
+        let mut sink = Vec::new();
+        encoder.process(&[0; 20], &mut sink).unwrap();
+        assert_eq!(sink.len(), 16);
+        let err = encoder.finish(&mut sink).unwrap_err();
+        assert!(matches!(err, Error::Truncated(_)), "{err:?}");
+        assert_eq!(encoder.finish(&mut sink).unwrap(), 0);
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_with(|| MatrixTransposeEncoder::new(10, 30, 2), &source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(MatrixTransposeEncoder::new(2, 2, 1), &[1, 2, 3, 4]);
+        test_finish_twice(MatrixTransposeDecoder::new(2, 2, 1), &[1, 2, 3, 4]);
+    }
+}
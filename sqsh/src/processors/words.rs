@@ -0,0 +1,72 @@
+//! Shared helper for processors working on little-endian words.
+use alloc::vec::Vec;
+
+/// Little-endian words of a fixed width split across `process` calls
+pub(crate) struct Words {
+    pub(crate) width: usize,
+    pub(crate) last: u64,
+    pub(crate) partial: Vec<u8>,
+}
+
+impl Words {
+    pub(crate) fn new(width: usize) -> Self {
+        assert!(
+            matches!(width, 1 | 2 | 4 | 8),
+            "Word width must be 1, 2, 4 or 8 bytes"
+        );
+        Words {
+            width,
+            last: 0,
+            partial: Vec::with_capacity(width),
+        }
+    }
+
+    /// Apply `op` on every complete word of the source
+    ///
+    /// The word is passed in the lower bytes of a `u64`, only the lower
+    /// `width` bytes of the result are written. `op` receives the current
+    /// word and the previous state and returns the word to write and the new
+    /// state.
+    pub(crate) fn apply<F: Fn(u64, u64) -> (u64, u64)>(
+        &mut self,
+        mut source: &[u8],
+        sink: &mut Vec<u8>,
+        op: F,
+    ) {
+        let width = self.width;
+        let word = |bytes: &[u8], last: &mut u64, sink: &mut Vec<u8>| {
+            let mut buffer = [0u8; 8];
+            buffer[..width].copy_from_slice(bytes);
+            let (result, state) = op(u64::from_le_bytes(buffer), *last);
+            *last = state;
+            sink.extend(&result.to_le_bytes()[..width]);
+        };
+        if !self.partial.is_empty() {
+            let missing = (width - self.partial.len()).min(source.len());
+            self.partial.extend(&source[..missing]);
+            source = &source[missing..];
+            if self.partial.len() < width {
+                return;
+            }
+            word(&self.partial, &mut self.last, sink);
+            self.partial.clear();
+        }
+        let mut words = source.chunks_exact(width);
+        for bytes in words.by_ref() {
+            word(bytes, &mut self.last, sink);
+        }
+        self.partial.extend(words.remainder());
+    }
+
+    /// Write the trailing partial word unchanged and reset the state
+    pub(crate) fn finish(&mut self, sink: &mut Vec<u8>) {
+        sink.append(&mut self.partial);
+        self.last = 0;
+    }
+
+    /// Reset the state for a new stream
+    pub(crate) fn reset(&mut self) {
+        self.last = 0;
+        self.partial.clear();
+    }
+}
@@ -0,0 +1,209 @@
+//! # Zig-Zag
+//!
+//! Zig-zag mapping of signed little-endian words onto unsigned words as used
+//! by [Protocol Buffers](https://protobuf.dev/programming-guides/encoding/#signed-ints).
+//! Small negative and positive values are interleaved as `0, -1, 1, -2, 2,
+//! ...`, so values of small magnitude end up with cleared high bits. This
+//! pairs well with a preceding `DeltaEncoder` whose differences alternate
+//! in sign.
+//!
+//! Words may be split across `process` calls. A trailing partial word at the
+//! end of the stream is written unchanged.
+use super::words::Words;
+use crate::core::{Decoder, Encoder, Invertible, Process, Reset};
+use crate::Result;
+use alloc::vec::Vec;
+use log::info;
+
+/// Interpret the lower `bits` of `word` as a two's complement number
+fn sign_extend(word: u64, bits: u32) -> i64 {
+    ((word << (64 - bits)) as i64) >> (64 - bits)
+}
+
+/// Zig-zag encoder for words of configurable width
+pub struct ZigZagEncoder {
+    state: Words,
+}
+
+impl ZigZagEncoder {
+    /// Create a new zig-zag encoder for single bytes
+    pub fn new() -> Self {
+        Self::with_width(1)
+    }
+
+    /// Create a new zig-zag encoder for little-endian words of `bytes` width
+    ///
+    /// Panics if the width is not 1, 2, 4 or 8.
+    pub fn with_width(bytes: usize) -> Self {
+        info!("New zig-zag encoder with width {bytes}");
+        ZigZagEncoder {
+            state: Words::new(bytes),
+        }
+    }
+}
+
+impl Default for ZigZagEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for ZigZagEncoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        let bits = 8 * self.state.width as u32;
+        self.state.apply(source, sink, |word, _| {
+            let n = sign_extend(word, bits);
+            (((n << 1) ^ (n >> 63)) as u64, 0)
+        });
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.finish(sink);
+        Ok(0)
+    }
+}
+
+impl Reset for ZigZagEncoder {
+    fn reset(&mut self) {
+        self.state.reset();
+    }
+}
+
+impl Encoder for ZigZagEncoder {}
+
+impl Invertible for ZigZagEncoder {
+    type Decoder = ZigZagDecoder;
+
+    fn inverse(&self) -> ZigZagDecoder {
+        ZigZagDecoder::with_width(self.state.width)
+    }
+}
+
+/// Zig-zag decoder restoring the signed words of the `ZigZagEncoder`
+pub struct ZigZagDecoder {
+    state: Words,
+}
+
+impl ZigZagDecoder {
+    /// Create a new zig-zag decoder for single bytes
+    pub fn new() -> Self {
+        Self::with_width(1)
+    }
+
+    /// Create a new zig-zag decoder for little-endian words of `bytes` width
+    ///
+    /// Panics if the width is not 1, 2, 4 or 8.
+    pub fn with_width(bytes: usize) -> Self {
+        info!("New zig-zag decoder with width {bytes}");
+        ZigZagDecoder {
+            state: Words::new(bytes),
+        }
+    }
+}
+
+impl Default for ZigZagDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Process for ZigZagDecoder {
+    fn process(&mut self, source: &[u8], sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.apply(source, sink, |word, _| {
+            ((word >> 1) ^ (word & 1).wrapping_neg(), 0)
+        });
+        Ok(source.len())
+    }
+    fn finish(&mut self, sink: &mut Vec<u8>) -> Result<usize> {
+        self.state.finish(sink);
+        Ok(0)
+    }
+}
+
+impl Reset for ZigZagDecoder {
+    fn reset(&mut self) {
+        self.state.reset();
+    }
+}
+
+impl Decoder for ZigZagDecoder {
+    type Encoder = ZigZagEncoder;
+}
+
+/// Decoder with the same word width as the encoder
+impl From<ZigZagEncoder> for ZigZagDecoder {
+    fn from(encoder: ZigZagEncoder) -> Self {
+        encoder.inverse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::process::tests::*;
+    use crate::core::Pipeline;
+    use crate::processors::DeltaEncoder;
+
+    #[test]
+    fn bytes() {
+        let source: Vec<u8> = [0i8, -1, 1, -2, 2, 127, -128]
+            .iter()
+            .map(|v| *v as u8)
+            .collect();
+        test_buffered_process::<ZigZagEncoder>(&source, &[0, 1, 2, 3, 4, 254, 255]);
+        test_buffered_process::<ZigZagDecoder>(&[0, 1, 2, 3, 4, 254, 255], &source);
+        let all: Vec<u8> = (0..=255).collect();
+        test_roundtrip(ZigZagEncoder::new(), &all);
+    }
+
+    #[test]
+    fn words() {
+        let source: Vec<u8> = [0i16, -1, 1, i16::MIN, i16::MAX]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let encoded = test_roundtrip(ZigZagEncoder::with_width(2), &source);
+        let expected: Vec<u8> = [0u16, 1, 2, u16::MAX, u16::MAX - 1]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        assert_eq!(encoded, expected);
+
+        let source: Vec<u8> = [-5i32, 70_000, i32::MIN]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let encoded = test_roundtrip(ZigZagEncoder::with_width(4), &source);
+        assert_eq!(encoded[..4], 9u32.to_le_bytes());
+        test_roundtrip(ZigZagEncoder::with_width(4), &source[..10]);
+    }
+
+    #[test]
+    fn after_delta() {
+        let source: Vec<u8> = [100u16, 98, 101, 99, 100]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let builder = Pipeline::builder()
+            .add(DeltaEncoder::with_width(2))
+            .add(ZigZagEncoder::with_width(2));
+        let mut decoder = builder.decoder_pipeline();
+        let mut encoder = builder.build();
+        let encoded = process_chunked(&mut encoder, &source, 3);
+        assert_eq!(encoded, [200, 0, 3, 0, 6, 0, 3, 0, 2, 0]);
+        assert_eq!(process_chunked(&mut decoder, &encoded, 3), source);
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = multi_chunk_source();
+        test_deterministic_process::<ZigZagEncoder>(&source);
+        test_deterministic_process::<ZigZagDecoder>(&source);
+    }
+
+    #[test]
+    fn finish_twice() {
+        test_finish_twice(ZigZagEncoder::with_width(2), "Wikipedia".as_bytes());
+        test_finish_twice(ZigZagDecoder::with_width(2), "Wikipedia".as_bytes());
+    }
+}
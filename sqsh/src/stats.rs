@@ -0,0 +1,316 @@
+//! # Statistics
+//!
+//! Helper functions for analysing data before compression. The results can
+//! be used to decide which processors are suitable for a given data source.
+
+/// Calculate the autocorrelation coefficients of `samples` for every lag
+/// from `0` to `max_lag` (inclusive).
+///
+/// The coefficient at index `k` is the correlation of the series with itself
+/// shifted by `k` samples. A high lag-1 coefficient indicates that a single
+/// delta is beneficial, a high lag-2 coefficient hints at a double delta.
+/// The coefficient at lag `0` is always `1.0`. Lags which exceed the length
+/// of the series, as well as all lags of a series without variance, have a
+/// coefficient of `0.0`.
+///
+/// ```
+/// use sqsh::stats::autocorrelation;
+///
+/// let ramp: Vec<f64> = (0..100).map(|x| x as f64).collect();
+/// let r = autocorrelation(&ramp, 2);
+/// assert_eq!(r.len(), 3);
+/// assert!(r[1] > 0.9);
+/// ```
+pub fn autocorrelation(samples: &[f64], max_lag: usize) -> Vec<f64> {
+    let mut result = vec![0.0; max_lag + 1];
+    result[0] = 1.0;
+    if samples.is_empty() {
+        return result;
+    }
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    let centered: Vec<f64> = samples.iter().map(|x| x - mean).collect();
+    let variance: f64 = centered.iter().map(|x| x * x).sum();
+    if variance == 0.0 {
+        return result;
+    }
+    for (lag, coefficient) in result.iter_mut().enumerate().skip(1) {
+        if lag >= centered.len() {
+            break;
+        }
+        let covariance: f64 = centered
+            .iter()
+            .zip(centered[lag..].iter())
+            .map(|(a, b)| a * b)
+            .sum();
+        *coefficient = covariance / variance;
+    }
+    result
+}
+
+/// Count the occurrences of every byte value in `data`
+///
+/// The histogram is computed in a single pass without building a
+/// `ProbTable`, so it is a cheap input for external entropy coders.
+///
+/// ```
+/// use sqsh::stats::byte_frequencies;
+///
+/// let counts = byte_frequencies(b"abracadabra");
+/// assert_eq!(counts[b'a' as usize], 5);
+/// assert_eq!(counts.iter().sum::<u64>(), 11);
+/// ```
+pub fn byte_frequencies(data: &[u8]) -> [u64; 256] {
+    let mut counts = [0u64; 256];
+    data.iter().for_each(|b| counts[*b as usize] += 1);
+    counts
+}
+
+/// Iterate over every byte of `data` together with its preceding context
+///
+/// The context holds the `order` bytes before the symbol. The first `order`
+/// symbols get the shorter contexts available, starting with an empty one,
+/// so every byte of `data` is yielded exactly once.
+///
+/// ```
+/// use sqsh::stats::context_windows;
+///
+/// let pairs: Vec<_> = context_windows(b"abc", 1).collect();
+/// assert_eq!(pairs, [(&b""[..], b'a'), (&b"a"[..], b'b'), (&b"b"[..], b'c')]);
+/// ```
+pub fn context_windows(data: &[u8], order: usize) -> impl Iterator<Item = (&[u8], u8)> {
+    data.iter()
+        .enumerate()
+        .map(move |(i, symbol)| (&data[i.saturating_sub(order)..i], *symbol))
+}
+
+/// Interpretation of raw bytes as little-endian numeric samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    /// Unsigned 8-bit integer
+    U8,
+    /// Signed 8-bit integer
+    I8,
+    /// Unsigned 16-bit integer
+    U16,
+    /// Signed 16-bit integer
+    I16,
+    /// Unsigned 32-bit integer
+    U32,
+    /// Signed 32-bit integer
+    I32,
+    /// Single precision float
+    F32,
+    /// Double precision float
+    F64,
+}
+
+impl ElementType {
+    /// Size of a single sample in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            ElementType::U8 | ElementType::I8 => 1,
+            ElementType::U16 | ElementType::I16 => 2,
+            ElementType::U32 | ElementType::I32 | ElementType::F32 => 4,
+            ElementType::F64 => 8,
+        }
+    }
+
+    /// Interpret a sample of `size()` bytes
+    fn value(&self, b: &[u8]) -> f64 {
+        match self {
+            ElementType::U8 => b[0] as f64,
+            ElementType::I8 => b[0] as i8 as f64,
+            ElementType::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            ElementType::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            ElementType::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            ElementType::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            ElementType::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            ElementType::F64 => f64::from_le_bytes(b.try_into().expect("8 bytes")),
+        }
+    }
+}
+
+/// Error introduced by a lossy processor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorReport {
+    /// Number of compared samples
+    pub samples: usize,
+    /// Maximum absolute error of a single sample
+    pub max_abs_error: f64,
+    /// Mean absolute error over all samples
+    pub mean_abs_error: f64,
+    /// Root mean squared error over all samples
+    pub rmse: f64,
+}
+
+/// Compare the `original` with the `decoded` data sample by sample
+///
+/// Both buffers are interpreted as little-endian samples of the given
+/// `element` type. An empty input yields a report without any error. A NaN
+/// sample makes all error measures NaN, even if it is equal in both buffers.
+///
+/// Panics if the buffers differ in length or the length is not a multiple
+/// of the element size.
+///
+/// ```
+/// use sqsh::stats::{lossy_error_report, ElementType};
+///
+/// let report = lossy_error_report(&[10, 20, 30], &[11, 20, 28], ElementType::U8);
+/// assert_eq!(report.max_abs_error, 2.0);
+/// assert_eq!(report.mean_abs_error, 1.0);
+/// ```
+pub fn lossy_error_report(original: &[u8], decoded: &[u8], element: ElementType) -> ErrorReport {
+    assert_eq!(original.len(), decoded.len(), "Buffers differ in length");
+    let size = element.size();
+    assert!(
+        original.len().is_multiple_of(size),
+        "Length is not a multiple of the element size"
+    );
+    let mut report = ErrorReport {
+        samples: original.len() / size,
+        max_abs_error: 0.0,
+        mean_abs_error: 0.0,
+        rmse: 0.0,
+    };
+    if report.samples == 0 {
+        return report;
+    }
+    let mut squared = 0.0;
+    for (o, d) in original.chunks_exact(size).zip(decoded.chunks_exact(size)) {
+        let error = (element.value(o) - element.value(d)).abs();
+        // `f64::max` would ignore a NaN error
+        if error > report.max_abs_error || error.is_nan() {
+            report.max_abs_error = error;
+        }
+        report.mean_abs_error += error;
+        squared += error * error;
+    }
+    report.mean_abs_error /= report.samples as f64;
+    report.rmse = (squared / report.samples as f64).sqrt();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ProbTable;
+
+    /// Simple xorshift generator for reproducible noise
+    fn noise(n: usize) -> Vec<f64> {
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        (0..n)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constant_slope() {
+        let ramp: Vec<f64> = (0..1000).map(|x| 3.0 * x as f64 + 7.0).collect();
+        let r = autocorrelation(&ramp, 2);
+        assert_eq!(r[0], 1.0);
+        assert!(r[1] > 0.99);
+        assert!(r[2] > 0.99);
+    }
+
+    #[test]
+    fn white_noise() {
+        let r = autocorrelation(&noise(10_000), 2);
+        assert_eq!(r[0], 1.0);
+        assert!(r[1].abs() < 0.05);
+        assert!(r[2].abs() < 0.05);
+    }
+
+    #[test]
+    fn degenerate() {
+        assert_eq!(autocorrelation(&[], 2), vec![1.0, 0.0, 0.0]);
+        assert_eq!(autocorrelation(&[4.0; 10], 1), vec![1.0, 0.0]);
+        assert_eq!(autocorrelation(&[1.0, 2.0], 3)[2..], [0.0, 0.0]);
+    }
+
+    #[test]
+    fn frequencies() {
+        let data: Vec<u8> = noise(5000).iter().map(|x| (x * 40.0) as i8 as u8).collect();
+        let counts = byte_frequencies(&data);
+        let table: ProbTable<u8> = data.iter().copied().collect();
+        for byte in 0..=u8::MAX {
+            let expected = table.count(&byte).unwrap_or(0) as u64;
+            assert_eq!(counts[byte as usize], expected);
+        }
+        assert_eq!(byte_frequencies(&[]), [0; 256]);
+    }
+
+    #[test]
+    fn contexts() {
+        let pairs: Vec<(&[u8], u8)> = context_windows(b"abcab", 2).collect();
+        let expected: [(&[u8], u8); 5] = [
+            (b"", b'a'),
+            (b"a", b'b'),
+            (b"ab", b'c'),
+            (b"bc", b'a'),
+            (b"ca", b'b'),
+        ];
+        assert_eq!(pairs, expected);
+        assert!(context_windows(b"ab", 0).all(|(c, _)| c.is_empty()));
+        assert_eq!(context_windows(&[], 3).count(), 0);
+    }
+
+    fn bytes_f32(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn bytes_i16(values: &[i16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn error_report_f32() {
+        let original = bytes_f32(&[1.0, -2.0, 3.5, 0.25]);
+        let decoded = bytes_f32(&[1.5, -2.0, 3.0, 0.25]);
+        let report = lossy_error_report(&original, &decoded, ElementType::F32);
+        assert_eq!(report.samples, 4);
+        assert_eq!(report.max_abs_error, 0.5);
+        assert_eq!(report.mean_abs_error, 0.25);
+        assert_eq!(report.rmse, 0.125f64.sqrt());
+    }
+
+    #[test]
+    fn error_report_i16() {
+        let original = bytes_i16(&[-300, 0, 32767]);
+        let decoded = bytes_i16(&[300, 0, 32764]);
+        let report = lossy_error_report(&original, &decoded, ElementType::I16);
+        assert_eq!(report.samples, 3);
+        assert_eq!(report.max_abs_error, 600.0);
+        assert_eq!(report.mean_abs_error, 201.0);
+        assert_eq!(report.rmse, (360_009.0f64 / 3.0).sqrt());
+    }
+
+    #[test]
+    fn error_report_lossless() {
+        let data = bytes_i16(&[1, 2, 3]);
+        let report = lossy_error_report(&data, &data, ElementType::U16);
+        assert_eq!(report.max_abs_error, 0.0);
+        assert_eq!(report.rmse, 0.0);
+        assert_eq!(lossy_error_report(&[], &[], ElementType::F64).samples, 0);
+    }
+
+    #[test]
+    fn error_report_nan() {
+        let original = bytes_f32(&[1.0, f32::NAN, 2.0]);
+        let decoded = bytes_f32(&[1.0, f32::NAN, 5.0]);
+        let report = lossy_error_report(&original, &decoded, ElementType::F32);
+        assert!(report.max_abs_error.is_nan());
+        assert!(report.mean_abs_error.is_nan());
+        assert!(report.rmse.is_nan());
+    }
+
+    #[test]
+    #[should_panic]
+    fn error_report_partial_element() {
+        lossy_error_report(&[0; 3], &[0; 3], ElementType::I16);
+    }
+}
@@ -0,0 +1,15 @@
+use std::process::Command;
+
+/// The library builds without the `std` feature
+#[test]
+fn build_without_std() {
+    let target = concat!(env!("CARGO_TARGET_TMPDIR"), "/no-std");
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--lib", "--no-default-features", "--manifest-path"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .env("CARGO_TARGET_DIR", target)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+}